@@ -0,0 +1,201 @@
+use log::{info, warn};
+use prometheus_exporter_base::{prelude::*, MissingValue};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+/// Labels the exporter sets itself on device-scoped metrics, they can not be
+/// overridden from the mapping file.
+const RESERVED_LABELS: [&str; 12] = [
+    "device_name",
+    "device_type",
+    "discovery_source",
+    "ip_address",
+    "mac_address",
+    "pool",
+    "vendor",
+    "randomized",
+    "alias",
+    "owner",
+    "room",
+    "le",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceAlias {
+    pub alias: Option<String>,
+    pub owner: Option<String>,
+    pub room: Option<String>,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Device aliases read from a TOML file with one table per MAC address or
+/// device key, reloaded when the file modification time changes.
+#[derive(Debug, Default)]
+pub struct DeviceAliases {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    entries: HashMap<String, DeviceAlias>,
+}
+
+impl DeviceAliases {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut aliases = DeviceAliases {
+            path,
+            ..Default::default()
+        };
+        aliases.reload();
+        aliases
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Reads the mapping file again if it changed since the last load. A
+    /// file that can not be parsed keeps the previous mapping.
+    pub fn reload(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse(&content))
+        {
+            Ok(entries) => {
+                info!(
+                    "Loaded {} device aliases from {}",
+                    entries.len(),
+                    path.display()
+                );
+                self.entries = entries;
+            }
+            Err(e) => warn!("Could not load device aliases {}: {}", path.display(), e),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DeviceAlias> {
+        self.entries.get(&key.to_ascii_lowercase())
+    }
+
+    /// Adds the alias, owner, room and custom labels of the device to the
+    /// instance. Nothing is added when no mapping file is configured.
+    pub fn with_labels<'a>(
+        &'a self,
+        instance: PrometheusInstance<'a, usize, MissingValue>,
+        key: &str,
+        device_name: &'a str,
+    ) -> PrometheusInstance<'a, usize, MissingValue> {
+        if !self.is_enabled() {
+            return instance;
+        }
+        let Some(alias) = self.get(key) else {
+            return instance
+                .with_label("alias", device_name)
+                .with_label("owner", "")
+                .with_label("room", "");
+        };
+        let mut instance = instance
+            .with_label("alias", alias.alias.as_deref().unwrap_or(device_name))
+            .with_label("owner", alias.owner.as_deref().unwrap_or(""))
+            .with_label("room", alias.room.as_deref().unwrap_or(""));
+        for (name, value) in &alias.labels {
+            instance = instance.with_label(name.as_str(), value.as_str());
+        }
+        instance
+    }
+}
+
+fn parse(content: &str) -> Result<HashMap<String, DeviceAlias>, String> {
+    let entries: BTreeMap<String, DeviceAlias> =
+        toml::from_str(content).map_err(|e| e.to_string())?;
+    entries
+        .into_iter()
+        .map(|(key, alias)| {
+            if let Some(name) = alias.labels.keys().find(|name| !is_valid_label(name)) {
+                return Err(format!("invalid label name {:?} for {}", name, key));
+            }
+            Ok((key.to_ascii_lowercase(), alias))
+        })
+        .collect()
+}
+
+fn is_valid_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__")
+        && !RESERVED_LABELS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = r#"
+["AA:BB:CC:DD:EE:01"]
+alias = "marie-laptop"
+owner = "marie"
+room = "office"
+labels = { kind = "laptop" }
+
+["ID-android-3f2a9c"]
+alias = "kitchen-tablet"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(MAPPING).unwrap();
+        assert_eq!(entries.len(), 2);
+        let alias = &entries["aa:bb:cc:dd:ee:01"];
+        assert_eq!(alias.alias.as_deref(), Some("marie-laptop"));
+        assert_eq!(alias.labels["kind"], "laptop");
+        assert_eq!(
+            entries["id-android-3f2a9c"].alias.as_deref(),
+            Some("kitchen-tablet")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_label() {
+        assert!(parse("[a]\nlabels = { device_name = \"x\" }").is_err());
+        assert!(parse("[a]\nlabels = { vendor = \"x\" }").is_err());
+        assert!(parse("[a]\nlabels = { \"1x\" = \"x\" }").is_err());
+        assert!(parse("[a]\nunknown = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_reload() {
+        let path =
+            std::env::temp_dir().join(format!("livebox-aliases-{}.toml", std::process::id()));
+        fs::write(&path, MAPPING).unwrap();
+        let mut aliases = DeviceAliases::new(Some(path.clone()));
+        assert!(aliases.get("AA:BB:CC:DD:EE:01").is_some());
+        assert!(aliases.get("AA:BB:CC:DD:EE:02").is_none());
+
+        fs::write(&path, "[\"AA:BB:CC:DD:EE:02\"]\nalias = \"tv\"\n").unwrap();
+        aliases.modified = None;
+        aliases.reload();
+        assert!(aliases.get("AA:BB:CC:DD:EE:01").is_none());
+        assert!(aliases.get("AA:BB:CC:DD:EE:02").is_some());
+
+        fs::write(&path, "not toml").unwrap();
+        aliases.modified = None;
+        aliases.reload();
+        assert!(aliases.get("AA:BB:CC:DD:EE:02").is_some());
+        fs::remove_file(&path).unwrap();
+    }
+}
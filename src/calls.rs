@@ -0,0 +1,148 @@
+use crate::livebox_client_rs::voip::VoiceCall;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub const CALL_LOG_STATE_FILE: &str = "calls.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CallLog {
+    pub cursor: Option<(String, String)>,
+    pub counts: BTreeMap<String, BTreeMap<String, u64>>,
+    #[serde(skip)]
+    pub recent: Vec<VoiceCall>,
+}
+
+impl CallLog {
+    /// Counts the calls newer than the cursor and moves the cursor forward.
+    /// Returns whether anything new was counted.
+    pub fn update(&mut self, calls: &[VoiceCall]) -> bool {
+        let mut calls = calls.to_vec();
+        calls.sort_by(|a, b| (&a.start_time, &a.call_id).cmp(&(&b.start_time, &b.call_id)));
+        let mut updated = false;
+        for call in &calls {
+            let position = (call.start_time.clone(), call.call_id.clone());
+            if self
+                .cursor
+                .as_ref()
+                .is_some_and(|cursor| &position <= cursor)
+            {
+                continue;
+            }
+            *self
+                .counts
+                .entry(call_direction(call).to_string())
+                .or_default()
+                .entry(call_result(call).to_string())
+                .or_default() += 1;
+            self.cursor = Some(position);
+            updated = true;
+        }
+        calls.reverse();
+        self.recent = calls;
+        updated
+    }
+
+    pub fn recent_json(&self) -> Value {
+        Value::Array(
+            self.recent
+                .iter()
+                .map(|call| {
+                    json!({
+                        "start_time": call.start_time,
+                        "duration": call.duration,
+                        "direction": call_direction(call),
+                        "result": call_result(call),
+                        "remote_number": mask_number(&call.remote_number),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+pub fn call_direction(call: &VoiceCall) -> &'static str {
+    if call.call_origin == "local" {
+        "outgoing"
+    } else {
+        "incoming"
+    }
+}
+
+pub fn call_result(call: &VoiceCall) -> &'static str {
+    match call.call_type.as_str() {
+        "succeeded" => "answered",
+        "missed" => "missed",
+        _ => "failed",
+    }
+}
+
+/// Keeps the first and last two digits of a phone number.
+pub fn mask_number(number: &str) -> String {
+    let chars: Vec<char> = number.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < 2 || i >= chars.len() - 2 {
+                *c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(call_id: &str, start_time: &str, call_origin: &str, call_type: &str) -> VoiceCall {
+        VoiceCall {
+            call_id: call_id.to_string(),
+            remote_number: "0100000042".to_string(),
+            start_time: start_time.to_string(),
+            duration: 10,
+            call_origin: call_origin.to_string(),
+            call_type: call_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_call_log_update() {
+        let mut call_log = CallLog::default();
+        let calls = vec![
+            call("2", "2024-03-26T11:00:00Z", "remote", "missed"),
+            call("1", "2024-03-26T10:00:00Z", "local", "succeeded"),
+        ];
+        assert!(call_log.update(&calls));
+        assert_eq!(call_log.counts["incoming"]["missed"], 1);
+        assert_eq!(call_log.counts["outgoing"]["answered"], 1);
+        assert!(!call_log.update(&calls));
+        assert_eq!(call_log.counts["incoming"]["missed"], 1);
+
+        let mut calls = calls;
+        calls.push(call("3", "2024-03-26T12:00:00Z", "remote", "missed"));
+        assert!(call_log.update(&calls));
+        assert_eq!(call_log.counts["incoming"]["missed"], 2);
+        assert_eq!(call_log.recent[0].call_id, "3");
+    }
+
+    #[test]
+    fn test_call_log_recent_json_masks_numbers() {
+        let mut call_log = CallLog::default();
+        call_log.update(&[call("1", "2024-03-26T10:00:00Z", "remote", "missed")]);
+        let recent = call_log.recent_json();
+        assert_eq!(recent[0]["remote_number"], "01******42");
+        assert_eq!(recent[0]["direction"], "incoming");
+    }
+
+    #[test]
+    fn test_mask_number() {
+        assert_eq!(mask_number("0100000042"), "01******42");
+        assert_eq!(mask_number("112"), "***");
+    }
+}
@@ -0,0 +1,6 @@
+use crate::tracker::ValueTracker;
+
+pub const FIRMWARE_STATE_FILE: &str = "firmware.json";
+
+/// Tracks the router software version.
+pub type FirmwareTracker = ValueTracker;
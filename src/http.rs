@@ -0,0 +1,51 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::{client::HttpConnector, Body, Client, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+
+pub type HttpsClient = Client<HttpsConnector<HttpConnector>, Body>;
+
+/// Client for outgoing notifications, accepting both http and https urls.
+pub fn https_client() -> HttpsClient {
+    let https = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder().build(https)
+}
+
+pub fn basic_auth(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", username, password))
+    )
+}
+
+/// Percent-encodes everything but the RFC 3986 unreserved characters, for
+/// values put in a url path or query.
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Checks that `url` is an absolute http or https url, the only ones the
+/// client can send a request to.
+pub fn parse_url(url: &str) -> Result<String, String> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| format!("invalid url {}: {}", url, e))?;
+    match uri.scheme_str() {
+        Some("http") | Some("https") if uri.host().is_some_and(|host| !host.is_empty()) => {
+            Ok(url.to_string())
+        }
+        _ => Err(format!("expected an http or https url, got {}", url)),
+    }
+}
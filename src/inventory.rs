@@ -0,0 +1,120 @@
+use crate::livebox_client_rs::devices::Device;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const INVENTORY_STATE_FILE: &str = "inventory.json";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InventoryDevice {
+    pub key: String,
+    pub name: String,
+    pub device_type: String,
+    pub ip_address: String,
+    pub first_seen: u64,
+    /// Notifier urls already reached while the device is pending, so that a
+    /// retry only goes to the notifiers that failed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notified: Vec<String>,
+    /// Sends that left some notifiers failing, the device is dropped after
+    /// `MAX_NOTIFICATION_ATTEMPTS` of them.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl InventoryDevice {
+    fn from_device(device: &Device, now: u64) -> Self {
+        InventoryDevice {
+            key: device.key.clone(),
+            name: device.name.clone(),
+            device_type: device.device_type.clone(),
+            ip_address: device.ip_address.clone().unwrap_or_default(),
+            first_seen: now,
+            notified: Vec::new(),
+            attempts: 0,
+        }
+    }
+}
+
+/// Every device ever seen on the network, keyed by lowercase MAC address or
+/// device key, with the new devices waiting to be notified.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeviceInventory {
+    pub devices: BTreeMap<String, InventoryDevice>,
+    pub new_total: u64,
+    pub pending: Vec<InventoryDevice>,
+    pub notifications: u64,
+    pub notification_failures: u64,
+}
+
+impl DeviceInventory {
+    /// Records the active devices never seen before and returns them. The
+    /// first call seeds the inventory with every device the router knows,
+    /// without reporting any of them.
+    pub fn observe(&mut self, devices: &[Device], now: u64) -> Vec<InventoryDevice> {
+        if self.devices.is_empty() {
+            for device in devices {
+                self.devices.insert(
+                    device.key.to_ascii_lowercase(),
+                    InventoryDevice::from_device(device, now),
+                );
+            }
+            return Vec::new();
+        }
+        let mut new_devices = Vec::new();
+        for device in devices.iter().filter(|d| d.active) {
+            let key = device.key.to_ascii_lowercase();
+            if self.devices.contains_key(&key) {
+                continue;
+            }
+            let entry = InventoryDevice::from_device(device, now);
+            self.devices.insert(key, entry.clone());
+            self.new_total += 1;
+            new_devices.push(entry);
+        }
+        new_devices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(key: &str, active: bool) -> Device {
+        Device {
+            key: key.to_string(),
+            name: format!("name-{}", key),
+            discovery_source: "dhcp".to_string(),
+            active,
+            device_type: "Computer".to_string(),
+            tags: "".to_string(),
+            ip_address: Some("192.168.1.10".to_string()),
+            ssid: None,
+            channel: None,
+            layer2_interface: None,
+        }
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut inventory = DeviceInventory::default();
+        let known = vec![
+            device("AA:BB:CC:DD:EE:01", true),
+            device("AA:BB:CC:DD:EE:02", false),
+        ];
+        assert!(inventory.observe(&known, 100).is_empty());
+        assert_eq!(inventory.devices.len(), 2);
+        assert_eq!(inventory.new_total, 0);
+
+        let mut devices = known.clone();
+        devices.push(device("aa:bb:cc:dd:ee:03", false));
+        assert!(inventory.observe(&devices, 200).is_empty());
+
+        devices.push(device("AA:BB:CC:DD:EE:03", true));
+        let new_devices = inventory.observe(&devices, 300);
+        assert_eq!(new_devices.len(), 1);
+        assert_eq!(new_devices[0].key, "AA:BB:CC:DD:EE:03");
+        assert_eq!(new_devices[0].first_seen, 300);
+        assert_eq!(inventory.new_total, 1);
+        assert!(inventory.observe(&devices, 400).is_empty());
+    }
+}
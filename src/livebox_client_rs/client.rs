@@ -1,19 +1,54 @@
 use crate::{
     livebox_client_rs::devices::Device,
-    livebox_client_rs::metrics::{DeviceMetrics, Metrics},
+    livebox_client_rs::dhcp::{DhcpLease, DhcpPool, DhcpPoolStatus, DhcpStaticLease},
+    livebox_client_rs::dyndns::DynDNSHost,
+    livebox_client_rs::events::LiveboxEvent,
+    livebox_client_rs::firewall::{
+        CustomRule, Dmz, FirewallConfiguration, PortForwardingRule, RespondToPing,
+    },
+    livebox_client_rs::iptv::IPTVStatus,
+    livebox_client_rs::ipv6::IPv6Status,
+    livebox_client_rs::lte::MobileStatus,
+    livebox_client_rs::metrics::{DeviceMetrics, HomeLanConfiguration, Metrics},
+    livebox_client_rs::resources::{MemoryStatus, ProcessStatus, SystemResources},
     livebox_client_rs::status::Status,
+    livebox_client_rs::voip::{VoiceCall, VoiceTrunk},
     livebox_client_rs::wan::WANConfiguration,
 };
 use cookie::Cookie;
+use futures_util::{stream, Stream};
 use hyper::{
     body::{Body, Bytes},
     client::HttpConnector,
     header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, SET_COOKIE},
-    Method, Request,
+    Method, Request, StatusCode,
 };
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+/// Delay before re-creating an eventmanager channel that failed.
+const EVENT_RETRY_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_secs(5)
+};
+
+/// Longest wait for an eventmanager answer. The router answers a long-poll
+/// well before, even without events, so a longer wait is a dropped connection.
+const EVENT_POLL_TIMEOUT: Duration = if cfg!(test) {
+    Duration::from_millis(200)
+} else {
+    Duration::from_secs(120)
+};
+
+enum EventError {
+    Unauthorized,
+    Channel(String),
+}
 
 #[derive(Debug, Clone)]
 pub struct Client {
@@ -147,6 +182,39 @@ impl Client {
         status
     }
 
+    /// Memory comes from `DeviceInfo.MemoryStatus`; CPU usage is only
+    /// exposed by some firmwares, through `DeviceInfo.ProcessStatus` or
+    /// else `sah.Device.Information`.
+    pub async fn get_system_resources(&self) -> SystemResources {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("DeviceInfo.MemoryStatus", "get", serde_json::json!({}))
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        let memory: MemoryStatus = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        let process = match self.get_process_status("DeviceInfo.ProcessStatus").await {
+            Some(process) => Some(process),
+            None => self.get_process_status("sah.Device.Information").await,
+        };
+        let resources = SystemResources { memory, process };
+        debug!("Deserialized system resources is: {:?}", resources);
+        resources
+    }
+
+    async fn get_process_status(&self, service: &str) -> Option<ProcessStatus> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(service, "get", serde_json::json!({}))
+            .await;
+        serde_json::from_slice::<Value>(&body_bytes)
+            .ok()
+            .filter(|_| parts.status.is_success())
+            .and_then(|json| serde_json::from_value(json["status"].clone()).ok())
+    }
+
     pub async fn get_wan_config(&self) -> WANConfiguration {
         let (parts, body_bytes) = self
             .authenticated_post_request("NMC", "getWANStatus", serde_json::json!({}))
@@ -162,6 +230,82 @@ impl Client {
         wan_config
     }
 
+    /// Reads `NMC.IPv6`, completing the address and delegated prefix from
+    /// the WAN status when the firmware does not report them there.
+    pub async fn get_ipv6_status(&self, wan_config: &WANConfiguration) -> IPv6Status {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("NMC.IPv6", "get", serde_json::json!({}))
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        let mut ipv6_status: IPv6Status = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        if ipv6_status.ipv6_address.is_empty() {
+            ipv6_status.ipv6_address = wan_config.ipv6_address.clone();
+        }
+        if ipv6_status.ipv6_delegated_prefix.is_empty() {
+            ipv6_status.ipv6_delegated_prefix = wan_config.ipv6_delegated_prefix.clone();
+        }
+        if ipv6_status.transition_mode.is_empty() {
+            let protocol = wan_config.protocol.to_lowercase();
+            if protocol.contains("dslite") {
+                ipv6_status.transition_mode = "DSLite".to_string();
+            } else if protocol.contains("6rd") {
+                ipv6_status.transition_mode = "6rd".to_string();
+            }
+        }
+        debug!("Deserialized ipv6 status is: {:?}", ipv6_status);
+        ipv6_status
+    }
+
+    pub async fn get_mobile_status(&self) -> MobileStatus {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("NMC.WWAN", "get", serde_json::json!({}))
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        let mobile_status: MobileStatus = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized mobile status is: {:?}", mobile_status);
+        mobile_status
+    }
+
+    pub async fn get_iptv_status(&self) -> IPTVStatus {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("NMC.OrangeTV", "getIPTVStatus", serde_json::json!({}))
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        let iptv_status: IPTVStatus = serde_json::from_value(json["data"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized iptv status is: {:?}", iptv_status);
+        iptv_status
+    }
+
+    pub async fn get_dyndns_hosts(&self) -> Vec<DynDNSHost> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("DynDNS", "getHosts", serde_json::json!({}))
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success() && json["status"].is_array(),
+            "Router answered with something else than a success code."
+        );
+        let hosts: Vec<DynDNSHost> = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized dyndns hosts is: {:?}", hosts);
+        hosts
+    }
+
     pub async fn get_devices(&self) -> Vec<Device> {
         let (parts, body_bytes) = self
             .authenticated_post_request("Devices", "get", serde_json::json!({}))
@@ -177,10 +321,200 @@ impl Client {
         devices
     }
 
+    pub async fn get_dhcp_pool(&self, pool: &str) -> DhcpPool {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(
+                "DHCPv4.Server",
+                "getDHCPServerPool",
+                serde_json::json!({"id": pool}),
+            )
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        let dhcp_pool: DhcpPool = serde_json::from_value(json["status"][pool].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized dhcp pool {} is: {:?}", pool, dhcp_pool);
+        dhcp_pool
+    }
+
+    pub async fn get_dhcp_leases(&self, pool: &str) -> Vec<DhcpLease> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(
+                &format!("DHCPv4.Server.Pool.{}", pool),
+                "getLeases",
+                serde_json::json!({}),
+            )
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success() && json["status"].is_object(),
+            "Router answered with something else than a success code."
+        );
+        let leases: Vec<DhcpLease> = json["status"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|lease| {
+                serde_json::from_value(lease.clone())
+                    .expect("Looks like the deserialized data is incomplete.")
+            })
+            .collect();
+        debug!("Deserialized dhcp leases of {} is: {:?}", pool, leases);
+        leases
+    }
+
+    pub async fn get_dhcp_static_leases(&self, pool: &str) -> Vec<DhcpStaticLease> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(
+                &format!("DHCPv4.Server.Pool.{}", pool),
+                "getStaticLeases",
+                serde_json::json!({}),
+            )
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success() && json["status"].is_array(),
+            "Router answered with something else than a success code."
+        );
+        let static_leases: Vec<DhcpStaticLease> = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!(
+            "Deserialized dhcp static leases of {} is: {:?}",
+            pool, static_leases
+        );
+        static_leases
+    }
+
+    pub async fn get_dhcp_pool_status(&self, pool: &str) -> DhcpPoolStatus {
+        DhcpPoolStatus {
+            name: pool.to_string(),
+            pool: self.get_dhcp_pool(pool).await,
+            leases: self.get_dhcp_leases(pool).await,
+            static_leases: self.get_dhcp_static_leases(pool).await,
+        }
+    }
+
+    async fn get_firewall_status(&self, method: &str, parameters: serde_json::Value) -> Value {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("Firewall", method, parameters)
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success(),
+            "Router answered with something else than a success code."
+        );
+        json["status"].clone()
+    }
+
+    async fn get_firewall_rules<T>(&self, method: &str) -> Vec<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self.get_firewall_status(method, json!({})).await {
+            Value::Object(rules) => rules
+                .into_iter()
+                .map(|(_, rule)| {
+                    serde_json::from_value(rule)
+                        .expect("Looks like the deserialized data is incomplete.")
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub async fn get_firewall_config(&self) -> FirewallConfiguration {
+        let ipv4_level = self
+            .get_firewall_status("getFirewallLevel", json!({}))
+            .await;
+        let ipv6_level = self
+            .get_firewall_status("getFirewallIPv6Level", json!({}))
+            .await;
+        let port_forwarding: Vec<PortForwardingRule> =
+            self.get_firewall_rules("getPortForwarding").await;
+        let custom_rules: Vec<CustomRule> = self.get_firewall_rules("getCustomRule").await;
+        let mut dmz: Vec<Dmz> = Vec::new();
+        if let Value::Object(entries) = self.get_firewall_status("getDMZ", json!({})).await {
+            for (origin, entry) in entries {
+                let mut entry: Dmz = serde_json::from_value(entry)
+                    .expect("Looks like the deserialized data is incomplete.");
+                entry.origin = origin;
+                dmz.push(entry);
+            }
+        }
+        let respond_to_ping: RespondToPing = serde_json::from_value(
+            self.get_firewall_status("getRespondToPing", json!({"sourceInterface": "data"}))
+                .await,
+        )
+        .expect("Looks like the deserialized data is incomplete.");
+        let firewall_config = FirewallConfiguration {
+            ipv4_level: ipv4_level.as_str().unwrap_or_default().to_string(),
+            ipv6_level: ipv6_level.as_str().unwrap_or_default().to_string(),
+            port_forwarding,
+            custom_rules,
+            dmz,
+            respond_to_ping,
+        };
+        debug!("Deserialized firewall config is: {:?}", firewall_config);
+        firewall_config
+    }
+
+    pub async fn get_voice_trunks(&self) -> Vec<VoiceTrunk> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(
+                "VoiceService.VoiceApplication",
+                "listTrunks",
+                serde_json::json!({}),
+            )
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success() && json["status"].is_array(),
+            "Router answered with something else than a success code."
+        );
+        let trunks: Vec<VoiceTrunk> = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized voice trunks is: {:?}", trunks);
+        trunks
+    }
+
+    pub async fn get_call_list(&self) -> Vec<VoiceCall> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request(
+                "VoiceService.VoiceApplication",
+                "getCallList",
+                serde_json::json!({}),
+            )
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
+        assert!(
+            parts.status.is_success() && json["status"].is_array(),
+            "Router answered with something else than a success code."
+        );
+        let calls: Vec<VoiceCall> = serde_json::from_value(json["status"].clone())
+            .expect("Looks like the deserialized data is incomplete.");
+        debug!("Deserialized call list is: {:?}", calls);
+        calls
+    }
+
     pub async fn get_metrics(&self) -> Vec<Metrics> {
-        let post_data = json!({"Seconds": 0, "NumberOfReadings": 1});
+        self.get_homelan_results("getResults", 1).await
+    }
+
+    pub async fn get_metrics_history(&self, readings: u32) -> Vec<Metrics> {
+        self.get_homelan_results("getResults", readings).await
+    }
+
+    pub async fn get_device_metrics(&self) -> Vec<Metrics> {
+        self.get_homelan_results("getDeviceResults", 1).await
+    }
+
+    async fn get_homelan_results(&self, method: &str, readings: u32) -> Vec<Metrics> {
+        let post_data = json!({"Seconds": 0, "NumberOfReadings": readings});
         let (_parts, body_bytes) = self
-            .authenticated_post_request("HomeLan", "getResults", post_data)
+            .authenticated_post_request("HomeLan", method, post_data)
             .await;
         let json: Value = serde_json::from_slice(&body_bytes).expect("Could not parse JSON.");
         let mut metrics: Vec<Metrics> = Vec::new();
@@ -197,6 +531,165 @@ impl Client {
         metrics
     }
 
+    /// Calls a HomeLan configuration method, `None` when the router answers
+    /// with an error, as firmwares without these methods do.
+    async fn get_homelan_status(
+        &self,
+        method: &str,
+        parameters: serde_json::Value,
+    ) -> Option<Value> {
+        let (parts, body_bytes) = self
+            .authenticated_post_request("HomeLan", method, parameters)
+            .await;
+        let json: Value = serde_json::from_slice(&body_bytes).ok()?;
+        if !parts.status.is_success() || json.get("errors").is_some() {
+            warn!("HomeLan {} failed: {}", method, json);
+            return None;
+        }
+        Some(json["status"].clone())
+    }
+
+    pub async fn get_reading_interval(&self) -> Option<u32> {
+        let status = self
+            .get_homelan_status("getReadingInterval", json!({}))
+            .await?;
+        serde_json::from_value(status).ok()
+    }
+
+    /// Returns whether the router accepted the new interval.
+    pub async fn set_reading_interval(&self, seconds: u32) -> bool {
+        trace!("Setting HomeLan reading interval to {}s.", seconds);
+        self.get_homelan_status("setReadingInterval", json!({"Interval": seconds}))
+            .await
+            .is_some_and(|status| status != Value::Bool(false))
+    }
+
+    pub async fn get_max_number_of_records(&self) -> Option<u32> {
+        let status = self
+            .get_homelan_status("getMaxNumberOfRecords", json!({}))
+            .await?;
+        serde_json::from_value(status).ok()
+    }
+
+    pub async fn get_interfaces_name(&self) -> Option<Vec<String>> {
+        let status = self
+            .get_homelan_status("getInterfacesName", json!({}))
+            .await?;
+        serde_json::from_value(status).ok()
+    }
+
+    pub async fn get_homelan_config(&self) -> Option<HomeLanConfiguration> {
+        let homelan_config = HomeLanConfiguration {
+            reading_interval: self.get_reading_interval().await?,
+            max_number_of_records: self.get_max_number_of_records().await?,
+            interfaces: self.get_interfaces_name().await?,
+        };
+        debug!("Deserialized homelan config is: {:?}", homelan_config);
+        Some(homelan_config)
+    }
+
+    /// Long-polls the eventmanager channel `channel_id`, 0 creates a new one.
+    /// Returns the channel to poll next and the events received.
+    async fn poll_events(
+        &self,
+        handlers: &[String],
+        channel_id: u64,
+    ) -> Result<(u64, Vec<LiveboxEvent>), EventError> {
+        let post_data = json!({
+            "events": handlers.iter().map(|h| json!({"handler": h})).collect::<Vec<_>>(),
+            "channelid": channel_id,
+        });
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(format!("http://{}/ws", self.ip))
+            .header(CONTENT_TYPE, "application/x-sah-event-4-call+json")
+            .header("X-Context", self.context_id.clone().unwrap_or_default())
+            .header(COOKIE, self.cookies.join("; "))
+            .body(Body::from(post_data.to_string()))
+            .expect("Could not build request.");
+        let body_bytes = tokio::time::timeout(EVENT_POLL_TIMEOUT, async {
+            let response = self
+                .client
+                .request(req)
+                .await
+                .map_err(|e| EventError::Channel(e.to_string()))?;
+            match response.status() {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                    return Err(EventError::Unauthorized)
+                }
+                status if !status.is_success() => {
+                    return Err(EventError::Channel(format!("router answered {}", status)))
+                }
+                _ => {}
+            }
+            hyper::body::to_bytes(response.into_body())
+                .await
+                .map_err(|e| EventError::Channel(e.to_string()))
+        })
+        .await
+        .map_err(|_| EventError::Channel("no answer from the router".to_string()))??;
+        let json: Value =
+            serde_json::from_slice(&body_bytes).map_err(|e| EventError::Channel(e.to_string()))?;
+        let channel_id = json["channelid"]
+            .as_u64()
+            .filter(|id| *id != 0)
+            .ok_or_else(|| EventError::Channel(format!("no channel in {}", json)))?;
+        let events = json["events"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|event| {
+                let data = &event["data"];
+                Some(LiveboxEvent {
+                    handler: data["handler"].as_str()?.to_string(),
+                    reason: data["object"]["reason"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    attributes: data["object"]["attributes"].clone(),
+                })
+            })
+            .collect();
+        debug!("Deserialized events are: {:?}", events);
+        Ok((channel_id, events))
+    }
+
+    /// Subscribes to the changes of the given handlers, e.g. `Devices.Device`,
+    /// `NMC` or `NeMo.Intf.data`. The channel is re-created when the router
+    /// drops it, and the client logs in again when its session expired.
+    pub fn subscribe(&self, handlers: &[&str]) -> impl Stream<Item = LiveboxEvent> {
+        let handlers: Vec<String> = handlers.iter().map(|h| h.to_string()).collect();
+        let state = (self.clone(), handlers, 0, VecDeque::new());
+        stream::unfold(
+            state,
+            |(mut client, handlers, mut channel_id, mut pending)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((event, (client, handlers, channel_id, pending)));
+                    }
+                    match client.poll_events(&handlers, channel_id).await {
+                        Ok((id, events)) => {
+                            channel_id = id;
+                            pending.extend(events);
+                        }
+                        Err(EventError::Unauthorized) => {
+                            warn!("Event channel session expired, logging in again.");
+                            channel_id = 0;
+                            tokio::time::sleep(EVENT_RETRY_DELAY).await;
+                            client.cookies.clear();
+                            client.login().await;
+                        }
+                        Err(EventError::Channel(e)) => {
+                            warn!("Event channel {} failed: {}", channel_id, e);
+                            channel_id = 0;
+                            tokio::time::sleep(EVENT_RETRY_DELAY).await;
+                        }
+                    }
+                }
+            },
+        )
+    }
+
     pub async fn logout(&mut self) {
         trace!("Logging out.");
         let post_data = json!({
@@ -230,6 +723,29 @@ mod tests {
     use httpmock::{Method::POST, MockServer};
     use serde_json::json;
 
+    fn get_mock_events() -> &'static str {
+        r#"{
+            "channelid": 7,
+            "events": [{
+                "data": {
+                    "handler": "Devices.Device.AA:BB:CC:DD:EE:01",
+                    "object": {
+                        "reason": "changed",
+                        "attributes": {"Active": false}
+                    }
+                }
+            }, {
+                "data": {
+                    "handler": "NMC",
+                    "object": {
+                        "reason": "changed",
+                        "attributes": {"WanState": "up"}
+                    }
+                }
+            }]
+        }"#
+    }
+
     fn get_mock_status() -> &'static str {
         r#"{
             "status": {
@@ -297,7 +813,8 @@ mod tests {
                 "Tags": "test",
                 "IPAddress": "test",
                 "SSID": "test",
-                "Channel": 11
+                "Channel": 11,
+                "Layer2Interface": "wl0"
             }]
         }"#
     }
@@ -316,6 +833,39 @@ mod tests {
         }"#
     }
 
+    fn get_mock_device_metrics() -> &'static str {
+        r#"{
+            "status":{
+                "AA:BB:CC:DD:EE:FF":{
+                    "Traffic":[{
+                        "Timestamp":1711483314,
+                        "Rx_Counter":1259440,
+                        "Tx_Counter":9696752
+                    }]
+                }
+            }
+        }"#
+    }
+
+    fn get_mock_voice_trunks() -> &'static str {
+        r#"{
+            "status": [{
+                "name": "SIP-Trunk",
+                "signalingProtocol": "SIP",
+                "enable": "Enabled",
+                "trunk_lines": [{
+                    "name": "LINE1",
+                    "groupId": "Group1",
+                    "enable": "Enabled",
+                    "status": "Up",
+                    "statusInfo": "",
+                    "directoryNumber": "+33100000000",
+                    "uri": "+33100000000@sip.test"
+                }]
+            }]
+        }"#
+    }
+
     #[tokio::test]
     async fn test_client_instantiation_default_gateway() {
         let password = "test_password";
@@ -377,6 +927,74 @@ mod tests {
         assert_eq!(status.manufacturer, "test");
     }
 
+    #[tokio::test]
+    async fn test_get_system_resources() {
+        let server = MockServer::start();
+        let _memory = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("DeviceInfo.MemoryStatus");
+            then.status(200)
+                .body(json!({"status": {"Total": 1024, "Free": 256}}).to_string());
+        });
+        let _process = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("DeviceInfo.ProcessStatus");
+            then.status(200).body(
+                json!({"status": null, "errors": [{"error": 196618, "description": "Object or parameter not found"}]})
+                    .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let resources = client.get_system_resources().await;
+        assert_eq!(resources.memory.total, 1024);
+        assert_eq!(resources.memory.free, 256);
+        assert!(resources.process.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_system_resources_from_device_information() {
+        let server = MockServer::start();
+        let _memory = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("DeviceInfo.MemoryStatus");
+            then.status(200)
+                .body(json!({"status": {"Total": 1024, "Free": 256}}).to_string());
+        });
+        let _process = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("DeviceInfo.ProcessStatus");
+            then.status(200).body(
+                json!({"status": null, "errors": [{"error": 196618, "description": "Object or parameter not found"}]})
+                    .to_string(),
+            );
+        });
+        let _information = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("sah.Device.Information");
+            then.status(200)
+                .body(json!({"status": {"CPUUsage": 42}}).to_string());
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let resources = client.get_system_resources().await;
+        assert_eq!(resources.process, Some(ProcessStatus { cpu_usage: 42 }));
+    }
+
     #[tokio::test]
     async fn test_get_wan_config() {
         let server = MockServer::start();
@@ -395,6 +1013,179 @@ mod tests {
         assert_eq!(wan.wan_state, "test");
     }
 
+    #[tokio::test]
+    async fn test_get_ipv6_status() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("NMC.IPv6");
+            then.status(200).body(
+                json!({"status": {
+                    "Enable": true,
+                    "UserRequested": true,
+                    "IPv4UserRequested": false,
+                    "PrefixValidLifetime": 3600
+                }})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let mut wan_config: WANConfiguration = serde_json::from_str::<Value>(get_mock_wan_config())
+            .map(|json| serde_json::from_value(json["data"].clone()).unwrap())
+            .unwrap();
+        wan_config.ipv6_delegated_prefix = "2001:db8:1234::/56".to_string();
+        wan_config.protocol = "dhcp_dslite".to_string();
+        let ipv6_status = client.get_ipv6_status(&wan_config).await;
+        assert!(ipv6_status.enable);
+        assert_eq!(ipv6_status.ipv6_address, "test");
+        assert_eq!(ipv6_status.prefix_length(), Some(56));
+        assert_eq!(ipv6_status.transition_mode, "DSLite");
+        assert_eq!(ipv6_status.prefix_preferred_lifetime, None);
+        assert_eq!(ipv6_status.prefix_valid_lifetime, Some(3600));
+    }
+
+    #[tokio::test]
+    async fn test_get_mobile_status() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("NMC.WWAN");
+            then.status(200).body(
+                json!({"status": {
+                    "Enable": true,
+                    "ConnectionStatus": "Connected",
+                    "OperatorName": "Orange F",
+                    "Technology": "LTE",
+                    "RSRP": -95,
+                    "RSRQ": -11,
+                    "SINR": 8.5,
+                    "BytesReceived": 1024,
+                    "BytesSent": 512
+                }})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let mobile_status = client.get_mobile_status().await;
+        assert!(mobile_status.is_connected());
+        assert_eq!(mobile_status.rsrp, Some(-95.0));
+        assert_eq!(mobile_status.sinr, Some(8.5));
+        assert_eq!(mobile_status.bytes_received, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_get_iptv_status() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getIPTVStatus");
+            then.status(200)
+                .body(json!({"status": true, "data": {"IPTVStatus": "Available"}}).to_string());
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let iptv_status = client.get_iptv_status().await;
+        assert!(iptv_status.is_available());
+    }
+
+    #[tokio::test]
+    async fn test_get_dyndns_hosts() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getHosts");
+            then.status(200).body(
+                json!({"status": [{
+                    "service": "No-IP",
+                    "hostname": "home.example.org",
+                    "username": "user",
+                    "password": "",
+                    "last_update": "2024-03-26T10:00:00Z",
+                    "status": "UPDATED",
+                    "enable": true
+                }]})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let hosts = client.get_dyndns_hosts().await;
+        assert_eq!(hosts[0].hostname, "home.example.org");
+        assert!(hosts[0].is_updated());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        use futures_util::StreamExt;
+
+        let server = MockServer::start();
+        let create = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("content-type", "application/x-sah-event-4-call+json")
+                .header("x-context", "test-context-id")
+                .body_contains("\"channelid\":0")
+                .body_contains("{\"handler\":\"Devices.Device\"}");
+            then.status(200).body(get_mock_events());
+        });
+        let expired = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .body_contains("\"channelid\":7");
+            then.status(500);
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let events: Vec<LiveboxEvent> = client
+            .subscribe(&["Devices.Device", "NMC"])
+            .take(4)
+            .collect()
+            .await;
+        assert_eq!(events[0].device_key(), Some("AA:BB:CC:DD:EE:01"));
+        assert_eq!(events[0].reason, "changed");
+        assert_eq!(events[0].attribute_bool("Active"), Some(false));
+        assert_eq!(events[1].handler, "NMC");
+        assert_eq!(events[1].attribute_str("WanState"), Some("up"));
+        assert_eq!(events[2], events[0]);
+        create.assert_hits(2);
+        expired.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_poll_events_timeout() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST).path("/ws");
+            then.status(200)
+                .delay(EVENT_POLL_TIMEOUT * 5)
+                .body(get_mock_events());
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        let result = client.poll_events(&["NMC".to_string()], 0).await;
+        assert!(matches!(result, Err(EventError::Channel(_))));
+    }
+
     #[tokio::test]
     async fn test_get_devices() {
         let server = MockServer::start();
@@ -431,6 +1222,297 @@ mod tests {
         assert_eq!(metrics[0].status["test"].traffic[0].timestamp, 1711483314);
     }
 
+    #[tokio::test]
+    async fn test_get_metrics_history() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .json_body(json!({
+                    "service": "HomeLan",
+                    "method": "getResults",
+                    "parameters": {"Seconds": 0, "NumberOfReadings": 2}
+                }));
+            then.status(200).body(
+                json!({"status": {"test": {"Traffic": [
+                    {"Timestamp": 1711483314, "Rx_Counter": 1, "Tx_Counter": 2},
+                    {"Timestamp": 1711483344, "Rx_Counter": 3, "Tx_Counter": 4}
+                ]}}})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let metrics: Vec<Metrics> = client.get_metrics_history(2).await;
+        assert_eq!(metrics[0].status["test"].traffic.len(), 2);
+        assert_eq!(metrics[0].status["test"].traffic[1].timestamp, 1711483344);
+    }
+
+    #[tokio::test]
+    async fn test_get_homelan_config() {
+        let server = MockServer::start();
+        let _interval = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getReadingInterval");
+            then.status(200).body(json!({"status": 30}).to_string());
+        });
+        let _records = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getMaxNumberOfRecords");
+            then.status(200).body(json!({"status": 2880}).to_string());
+        });
+        let _interfaces = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getInterfacesName");
+            then.status(200)
+                .body(json!({"status": ["eth0", "wl0"]}).to_string());
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let homelan_config = client.get_homelan_config().await.unwrap();
+        assert_eq!(homelan_config.reading_interval, 30);
+        assert_eq!(homelan_config.max_number_of_records, 2880);
+        assert_eq!(homelan_config.interfaces, vec!["eth0", "wl0"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_reading_interval() {
+        let server = MockServer::start();
+        let m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .json_body(json!({
+                    "service": "HomeLan",
+                    "method": "setReadingInterval",
+                    "parameters": {"Interval": 60}
+                }));
+            then.status(200).body(json!({"status": true}).to_string());
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        assert!(client.set_reading_interval(60).await);
+        m.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_homelan_config_unsupported() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id");
+            then.status(200).body(
+                json!({"errors": [{"error": 13, "description": "Function not found"}]}).to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        assert_eq!(client.get_homelan_config().await, None);
+        assert!(!client.set_reading_interval(60).await);
+    }
+
+    #[tokio::test]
+    async fn test_get_device_metrics() {
+        let server = MockServer::start();
+        let mock_device_metrics = get_mock_device_metrics();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getDeviceResults");
+            then.status(200).body(mock_device_metrics);
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let metrics: Vec<Metrics> = client.get_device_metrics().await;
+        assert_eq!(
+            metrics[0].status["AA:BB:CC:DD:EE:FF"].traffic[0].rx_counter,
+            1259440
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_voice_trunks() {
+        let server = MockServer::start();
+        let mock_voice_trunks = get_mock_voice_trunks();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("listTrunks");
+            then.status(200).body(mock_voice_trunks);
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let trunks: Vec<VoiceTrunk> = client.get_voice_trunks().await;
+        assert_eq!(trunks[0].signaling_protocol, "SIP");
+        assert_eq!(trunks[0].trunk_lines[0].status, "Up");
+        assert_eq!(trunks[0].trunk_lines[0].directory_number, "+33100000000");
+    }
+
+    #[tokio::test]
+    async fn test_get_call_list() {
+        let server = MockServer::start();
+        let _m = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getCallList");
+            then.status(200).body(
+                json!({"status": [{
+                    "callId": "1",
+                    "remoteNumber": "0100000000",
+                    "remoteName": "",
+                    "startTime": "2024-03-26T10:00:00Z",
+                    "duration": 42,
+                    "callOrigin": "remote",
+                    "callType": "missed"
+                }]})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let calls: Vec<VoiceCall> = client.get_call_list().await;
+        assert_eq!(calls[0].call_type, "missed");
+        assert_eq!(calls[0].duration, 42);
+    }
+
+    #[tokio::test]
+    async fn test_get_dhcp_pool_status() {
+        let server = MockServer::start();
+        let _pool = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getDHCPServerPool");
+            then.status(200).body(
+                json!({"status": {"guest": {
+                    "Enable": true,
+                    "Status": "Enabled",
+                    "MinAddress": "192.168.144.100",
+                    "MaxAddress": "192.168.144.149",
+                    "LeaseTime": 3600
+                }}})
+                .to_string(),
+            );
+        });
+        let _leases = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getLeases");
+            then.status(200).body(
+                json!({"status": {"AA:BB:CC:DD:EE:FF": {
+                    "MACAddress": "AA:BB:CC:DD:EE:FF",
+                    "IPAddress": "192.168.144.100",
+                    "LeaseTimeRemaining": 1800,
+                    "Active": true,
+                    "FriendlyName": "phone"
+                }}})
+                .to_string(),
+            );
+        });
+        let _static_leases = server.mock(|when, then| {
+            when.method(POST)
+                .path("/ws")
+                .header("x-context", "test-context-id")
+                .body_contains("getStaticLeases");
+            then.status(200).body(
+                json!({"status": [{
+                    "MACAddress": "11:22:33:44:55:66",
+                    "IPAddress": "192.168.144.10",
+                    "LeasePath": "DHCPv4.Server.Pool.guest.StaticAddress.1."
+                }]})
+                .to_string(),
+            );
+        });
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let pool_status = client.get_dhcp_pool_status("guest").await;
+        assert_eq!(pool_status.pool.size(), 50);
+        assert_eq!(pool_status.leases[0].lease_time_remaining, 1800);
+        assert_eq!(pool_status.static_leases[0].ip_address, "192.168.144.10");
+    }
+
+    #[tokio::test]
+    async fn test_get_firewall_config() {
+        let server = MockServer::start();
+        let responses = [
+            ("getFirewallLevel", json!({"status": "Medium"})),
+            ("getFirewallIPv6Level", json!({"status": "High"})),
+            (
+                "getPortForwarding",
+                json!({"status": {"webui_ssh": {
+                    "Id": "webui_ssh",
+                    "Origin": "webui",
+                    "Description": "ssh",
+                    "Protocol": "6",
+                    "ExternalPort": "2222",
+                    "InternalPort": "22",
+                    "DestinationIPAddress": "192.168.1.10",
+                    "Enable": true
+                }}}),
+            ),
+            ("getCustomRule", json!({"status": {}})),
+            (
+                "getDMZ",
+                json!({"status": {"webui": {
+                    "DestinationIPAddress": "192.168.1.20",
+                    "Enable": true
+                }}}),
+            ),
+            (
+                "getRespondToPing",
+                json!({"status": {"enableIPv4": false, "enableIPv6": true}}),
+            ),
+        ];
+        for (method, response) in responses {
+            server.mock(|when, then| {
+                when.method(POST)
+                    .path("/ws")
+                    .header("x-context", "test-context-id")
+                    .body_contains(format!("\"{}\"", method));
+                then.status(200).body(response.to_string());
+            });
+        }
+        let mut client = Client::new("password", "192.168.1.1");
+        client.ip = server.address().to_string();
+        client.cookies.push("session=mocked_session_id".to_string());
+        client.context_id = Some("test-context-id".to_string());
+        let firewall_config = client.get_firewall_config().await;
+        assert_eq!(firewall_config.ipv4_level, "Medium");
+        assert_eq!(firewall_config.ipv6_level, "High");
+        assert_eq!(firewall_config.port_forwarding[0].internal_port, "22");
+        assert!(firewall_config.custom_rules.is_empty());
+        assert_eq!(firewall_config.dmz[0].origin, "webui");
+        assert!(firewall_config.respond_to_ping.enable_ipv6);
+    }
+
     #[tokio::test]
     async fn test_logout() {
         let server = MockServer::start();
@@ -14,4 +14,11 @@ pub struct Device {
     #[serde(rename(deserialize = "SSID"))]
     pub ssid: Option<String>,
     pub channel: Option<u32>,
+    pub layer2_interface: Option<String>,
+}
+
+impl Device {
+    pub fn is_iptv(&self) -> bool {
+        self.tags.split_whitespace().any(|tag| tag == "iptv")
+    }
 }
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use std::net::Ipv4Addr;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhcpPool {
+    pub enable: bool,
+    pub status: String,
+    pub min_address: String,
+    pub max_address: String,
+    pub lease_time: u32,
+}
+
+impl DhcpPool {
+    pub fn size(&self) -> u32 {
+        match (
+            self.min_address.parse::<Ipv4Addr>(),
+            self.max_address.parse::<Ipv4Addr>(),
+        ) {
+            (Ok(min), Ok(max)) => u32::from(max)
+                .checked_sub(u32::from(min))
+                .map_or(0, |size| size + 1),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhcpLease {
+    #[serde(rename(deserialize = "MACAddress"))]
+    pub mac_address: String,
+    #[serde(rename(deserialize = "IPAddress"))]
+    pub ip_address: String,
+    pub lease_time_remaining: i64,
+    pub active: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct DhcpStaticLease {
+    #[serde(rename(deserialize = "MACAddress"))]
+    pub mac_address: String,
+    #[serde(rename(deserialize = "IPAddress"))]
+    pub ip_address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpPoolStatus {
+    pub name: String,
+    pub pool: DhcpPool,
+    pub leases: Vec<DhcpLease>,
+    pub static_leases: Vec<DhcpStaticLease>,
+}
@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct DynDNSHost {
+    pub service: String,
+    pub hostname: String,
+    pub status: String,
+    pub last_update: String,
+    pub enable: bool,
+}
+
+impl DynDNSHost {
+    pub fn is_updated(&self) -> bool {
+        self.status == "UPDATED"
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A change notified on an eventmanager channel, e.g. the `Devices.Device.<key>`
+/// handler with the `changed` reason and the new `Active` attribute.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct LiveboxEvent {
+    pub handler: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub attributes: Value,
+}
+
+impl LiveboxEvent {
+    /// Key of the device a `Devices.Device` event is about.
+    pub fn device_key(&self) -> Option<&str> {
+        self.handler
+            .strip_prefix("Devices.Device.")
+            .filter(|key| !key.is_empty())
+    }
+
+    pub fn attribute_str(&self, name: &str) -> Option<&str> {
+        self.attributes[name].as_str()
+    }
+
+    pub fn attribute_bool(&self, name: &str) -> Option<bool> {
+        self.attributes[name].as_bool()
+    }
+}
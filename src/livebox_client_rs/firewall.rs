@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PortForwardingRule {
+    pub id: String,
+    pub origin: String,
+    #[serde(default)]
+    pub description: String,
+    pub protocol: String,
+    pub external_port: String,
+    pub internal_port: String,
+    #[serde(rename(deserialize = "DestinationIPAddress"))]
+    pub destination_ip_address: String,
+    pub enable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct CustomRule {
+    pub id: String,
+    pub action: String,
+    pub protocol: String,
+    pub destination_port: String,
+    pub source_port: String,
+    pub destination_prefix: String,
+    pub source_prefix: String,
+    #[serde(rename(deserialize = "IPVersion"))]
+    pub ip_version: u8,
+    pub enable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Dmz {
+    pub origin: String,
+    #[serde(rename(deserialize = "DestinationIPAddress"))]
+    pub destination_ip_address: String,
+    pub enable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RespondToPing {
+    #[serde(rename(deserialize = "enableIPv4"))]
+    pub enable_ipv4: bool,
+    #[serde(rename(deserialize = "enableIPv6"))]
+    pub enable_ipv6: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FirewallConfiguration {
+    pub ipv4_level: String,
+    pub ipv6_level: String,
+    pub port_forwarding: Vec<PortForwardingRule>,
+    pub custom_rules: Vec<CustomRule>,
+    pub dmz: Vec<Dmz>,
+    pub respond_to_ping: RespondToPing,
+}
+
+impl FirewallConfiguration {
+    /// FNV-1a hash of a fixed list of fields, so that any change on the
+    /// router shows up as a new value. Fields added to these structs later
+    /// are not hashed, and do not change the value of existing setups.
+    pub fn config_hash(&self) -> u32 {
+        let mut fields = vec![
+            format!("level|{}|{}", self.ipv4_level, self.ipv6_level),
+            format!(
+                "ping|{}|{}",
+                self.respond_to_ping.enable_ipv4, self.respond_to_ping.enable_ipv6
+            ),
+        ];
+        fields.extend(self.port_forwarding.iter().map(|r| {
+            format!(
+                "forward|{}|{}|{}|{}|{}|{}|{}|{}",
+                r.id,
+                r.origin,
+                r.description,
+                r.protocol,
+                r.external_port,
+                r.internal_port,
+                r.destination_ip_address,
+                r.enable
+            )
+        }));
+        fields.extend(self.custom_rules.iter().map(|r| {
+            format!(
+                "rule|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+                r.id,
+                r.action,
+                r.protocol,
+                r.destination_port,
+                r.source_port,
+                r.destination_prefix,
+                r.source_prefix,
+                r.ip_version,
+                r.enable
+            )
+        }));
+        fields.extend(
+            self.dmz
+                .iter()
+                .map(|d| format!("dmz|{}|{}|{}", d.origin, d.destination_ip_address, d.enable)),
+        );
+        fields.sort();
+        fields.join("\n").bytes().fold(0x811c9dc5, |hash, byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+        })
+    }
+}
+
+pub fn protocol_name(protocol: &str) -> &str {
+    match protocol {
+        "6" => "tcp",
+        "17" => "udp",
+        "6,17" | "17,6" => "tcp,udp",
+        "1" => "icmp",
+        _ => protocol,
+    }
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct IPTVStatus {
+    #[serde(rename(deserialize = "IPTVStatus"))]
+    pub iptv_status: String,
+}
+
+impl IPTVStatus {
+    pub fn is_available(&self) -> bool {
+        self.iptv_status == "Available"
+    }
+}
@@ -0,0 +1,31 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct IPv6Status {
+    pub enable: bool,
+    pub user_requested: bool,
+    #[serde(rename(deserialize = "IPv6Address"))]
+    pub ipv6_address: String,
+    pub address_status: String,
+    #[serde(rename(deserialize = "IPv6DelegatedPrefix"))]
+    pub ipv6_delegated_prefix: String,
+    pub prefix_preferred_lifetime: Option<u32>,
+    pub prefix_valid_lifetime: Option<u32>,
+    pub transition_mode: String,
+}
+
+impl IPv6Status {
+    pub fn prefix_length(&self) -> Option<u8> {
+        self.ipv6_delegated_prefix
+            .rsplit_once('/')
+            .and_then(|(_, length)| length.parse().ok())
+    }
+
+    /// The address is usable once it is no longer tentative or deprecated.
+    pub fn is_up(&self) -> bool {
+        self.enable
+            && !self.ipv6_address.is_empty()
+            && matches!(self.address_status.as_str(), "" | "Preferred")
+    }
+}
@@ -0,0 +1,44 @@
+use crate::livebox_client_rs::wan::WANConfiguration;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct MobileStatus {
+    pub enable: bool,
+    pub connection_status: String,
+    pub operator_name: String,
+    pub technology: String,
+    #[serde(rename(deserialize = "RSRP"))]
+    pub rsrp: Option<f64>,
+    #[serde(rename(deserialize = "RSRQ"))]
+    pub rsrq: Option<f64>,
+    #[serde(rename(deserialize = "SINR"))]
+    pub sinr: Option<f64>,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+impl MobileStatus {
+    pub fn is_connected(&self) -> bool {
+        self.connection_status == "Connected"
+    }
+
+    /// Routers without a 4G backup answer with an empty status.
+    pub fn has_data(&self) -> bool {
+        !self.operator_name.is_empty()
+            || !self.technology.is_empty()
+            || self.bytes_received > 0
+            || self.bytes_sent > 0
+    }
+
+    /// Traffic goes through the mobile link either when the router reports
+    /// it as the WAN link, or when the primary link is down while the mobile
+    /// one is connected.
+    pub fn is_backup_active(&self, wan_config: &WANConfiguration) -> bool {
+        let link_type = wan_config.link_type.to_lowercase();
+        ["wwan", "lte", "mobile", "cellular"]
+            .iter()
+            .any(|t| link_type.contains(t))
+            || (self.is_connected() && wan_config.link_state != "up")
+    }
+}
@@ -21,3 +21,10 @@ pub struct TrafficData {
     #[serde(rename(deserialize = "Timestamp"))]
     pub timestamp: u64,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HomeLanConfiguration {
+    pub reading_interval: u32,
+    pub max_number_of_records: u32,
+    pub interfaces: Vec<String>,
+}
@@ -1,5 +1,14 @@
 pub mod client;
 pub mod devices;
+pub mod dhcp;
+pub mod dyndns;
+pub mod events;
+pub mod firewall;
+pub mod iptv;
+pub mod ipv6;
+pub mod lte;
 pub mod metrics;
+pub mod resources;
 pub mod status;
+pub mod voip;
 pub mod wan;
@@ -0,0 +1,21 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct MemoryStatus {
+    pub total: u64,
+    pub free: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProcessStatus {
+    #[serde(rename(deserialize = "CPUUsage"))]
+    pub cpu_usage: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemResources {
+    pub memory: MemoryStatus,
+    pub process: Option<ProcessStatus>,
+}
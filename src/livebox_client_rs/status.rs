@@ -40,3 +40,15 @@ pub struct Status {
     #[serde(rename(deserialize = "BaseMAC"))]
     pub base_mac: String,
 }
+
+impl Status {
+    /// Returns the first use date as a UNIX timestamp, the router reports
+    /// `0001-01-01T00:00:00Z` when it never knew the time.
+    pub fn first_use_timestamp(&self) -> Option<u64> {
+        humantime::parse_rfc3339_weak(&self.first_use_date)
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs())
+    }
+}
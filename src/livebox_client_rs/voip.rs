@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTrunk {
+    pub name: String,
+    pub signaling_protocol: String,
+    pub enable: String,
+    #[serde(rename(deserialize = "trunk_lines"))]
+    pub trunk_lines: Vec<VoiceLine>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceLine {
+    pub name: String,
+    pub enable: String,
+    pub status: String,
+    pub status_info: String,
+    pub directory_number: String,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceCall {
+    pub call_id: String,
+    pub remote_number: String,
+    pub start_time: String,
+    pub duration: u32,
+    pub call_origin: String,
+    pub call_type: String,
+}
@@ -1,5 +1,29 @@
 use serde::Deserialize;
 
+/// Connection states reported by the router in `ConnectionState`.
+pub const CONNECTION_STATES: [&str; 8] = [
+    "Unconfigured",
+    "Connecting",
+    "Authenticating",
+    "Connected",
+    "PendingDisconnect",
+    "Disconnecting",
+    "Disconnected",
+    "Bound",
+];
+
+/// Errors reported by the router in `LastConnectionError`.
+pub const CONNECTION_ERRORS: [&str; 8] = [
+    "ERROR_NONE",
+    "ERROR_UNKNOWN",
+    "ERROR_AUTHENTICATION_FAILURE",
+    "ERROR_NO_CARRIER",
+    "ERROR_NO_ANSWER",
+    "ERROR_IDLE_DISCONNECT",
+    "ERROR_USER_DISCONNECT",
+    "ERROR_ISP_TIME_OUT",
+];
+
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "PascalCase")]
 pub struct WANConfiguration {
@@ -0,0 +1,669 @@
+use crate::{
+    livebox_client_rs::{
+        metrics::{Metrics, TrafficData},
+        status::Status,
+        wan::WANConfiguration,
+    },
+    presence::PresenceTracker,
+};
+use log::{debug, warn};
+use rumqttc::{AsyncClient, ClientError, ConnectionError, LastWill, MqttOptions, QoS};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, net::Ipv6Addr, time::Duration};
+
+const AVAILABILITY_ONLINE: &str = "online";
+const AVAILABILITY_OFFLINE: &str = "offline";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub credentials: Option<(String, String)>,
+    pub topic_prefix: String,
+    pub discovery_prefix: String,
+    pub retain: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: String,
+    pub retain: bool,
+}
+
+impl MqttMessage {
+    fn new(topic: String, payload: impl Into<String>, retain: bool) -> Self {
+        MqttMessage {
+            topic,
+            payload: payload.into(),
+            retain,
+        }
+    }
+}
+
+/// Parses `host[:port]`, the port defaults to 1883. IPv6 addresses are given
+/// as `[address]:port` or bare, and kept in brackets for the connection.
+pub fn parse_broker(broker: &str) -> Result<(String, u16), String> {
+    let broker = broker.trim_start_matches("mqtt://");
+    let parse_port = |port: &str| {
+        port.parse()
+            .map_err(|e| format!("invalid port {}: {}", port, e))
+    };
+    if let Some(rest) = broker.strip_prefix('[') {
+        let (address, port) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("expected [address]:port, got {}", broker))?;
+        address
+            .parse::<Ipv6Addr>()
+            .map_err(|e| format!("invalid IPv6 address {}: {}", address, e))?;
+        let port = match port.strip_prefix(':') {
+            Some(port) => parse_port(port)?,
+            None if port.is_empty() => 1883,
+            None => return Err(format!("expected [address]:port, got {}", broker)),
+        };
+        return Ok((format!("[{}]", address), port));
+    }
+    if let Ok(address) = broker.parse::<Ipv6Addr>() {
+        return Ok((format!("[{}]", address), 1883));
+    }
+    match broker.rsplit_once(':') {
+        Some((host, _)) if host.contains(':') => Err(format!(
+            "expected [address]:port for an IPv6 broker, got {}",
+            broker
+        )),
+        Some((host, port)) => Ok((host.to_string(), parse_port(port)?)),
+        None if !broker.is_empty() => Ok((broker.to_string(), 1883)),
+        None => Err("expected host[:port]".to_string()),
+    }
+}
+
+/// Home Assistant object ids only allow `[a-zA-Z0-9_-]`.
+fn object_id(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The latest reading of every interface.
+fn latest_traffic(metrics: &[Metrics]) -> BTreeMap<&str, &TrafficData> {
+    let mut traffic: BTreeMap<&str, &TrafficData> = BTreeMap::new();
+    for metric in metrics {
+        for (interface_name, interface_data) in &metric.status {
+            for entry in &interface_data.traffic {
+                let latest = traffic.entry(interface_name).or_insert(entry);
+                if entry.timestamp > latest.timestamp {
+                    *latest = entry;
+                }
+            }
+        }
+    }
+    traffic
+}
+
+impl MqttConfig {
+    fn availability_topic(&self) -> String {
+        format!("{}/availability", self.topic_prefix)
+    }
+
+    fn state_topic(&self) -> String {
+        format!("{}/state", self.topic_prefix)
+    }
+
+    fn presence_topic(&self, key: &str) -> String {
+        format!("{}/presence/{}", self.topic_prefix, object_id(key))
+    }
+
+    fn discovery_topic(&self, component: &str, node_id: &str, object: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/config",
+            self.discovery_prefix, component, node_id, object
+        )
+    }
+
+    /// Home Assistant discovery configs, the Livebox is one device, keyed by
+    /// its serial number, with a sensor per value and a device_tracker per
+    /// tracked device.
+    pub fn discovery_messages(
+        &self,
+        status: &Status,
+        metrics: &[Metrics],
+        presence: &PresenceTracker,
+    ) -> Vec<MqttMessage> {
+        let node_id = format!("livebox_{}", object_id(&status.serial_number));
+        let device = json!({
+            "identifiers": [node_id],
+            "name": "Livebox",
+            "manufacturer": status.manufacturer,
+            "model": status.model_name,
+            "hw_version": status.hardware_version,
+            "sw_version": status.software_version,
+            "serial_number": status.serial_number,
+        });
+        let entity = |object: &str, name: &str, fields: Value| {
+            let mut config = Map::new();
+            config.insert("name".to_string(), json!(name));
+            config.insert(
+                "unique_id".to_string(),
+                json!(format!("{}_{}", node_id, object)),
+            );
+            config.insert(
+                "availability_topic".to_string(),
+                json!(self.availability_topic()),
+            );
+            config.insert("device".to_string(), device.clone());
+            if let Value::Object(fields) = fields {
+                config.extend(fields);
+            }
+            Value::Object(config).to_string()
+        };
+        let state_topic = self.state_topic();
+        let mut messages = vec![
+            MqttMessage::new(
+                self.discovery_topic("binary_sensor", &node_id, "wan"),
+                entity(
+                    "wan",
+                    "WAN",
+                    json!({
+                        "state_topic": state_topic,
+                        "value_template": "{{ 'ON' if value_json.wan_state == 'up' else 'OFF' }}",
+                        "device_class": "connectivity",
+                    }),
+                ),
+                true,
+            ),
+            MqttMessage::new(
+                self.discovery_topic("sensor", &node_id, "uptime"),
+                entity(
+                    "uptime",
+                    "Uptime",
+                    json!({
+                        "state_topic": state_topic,
+                        "value_template": "{{ value_json.uptime }}",
+                        "device_class": "duration",
+                        "unit_of_measurement": "s",
+                        "entity_category": "diagnostic",
+                    }),
+                ),
+                true,
+            ),
+            MqttMessage::new(
+                self.discovery_topic("sensor", &node_id, "reboots"),
+                entity(
+                    "reboots",
+                    "Reboots",
+                    json!({
+                        "state_topic": state_topic,
+                        "value_template": "{{ value_json.reboots }}",
+                        "state_class": "total_increasing",
+                        "entity_category": "diagnostic",
+                    }),
+                ),
+                true,
+            ),
+            MqttMessage::new(
+                self.discovery_topic("sensor", &node_id, "connection_state"),
+                entity(
+                    "connection_state",
+                    "Connection state",
+                    json!({
+                        "state_topic": state_topic,
+                        "value_template": "{{ value_json.connection_state }}",
+                    }),
+                ),
+                true,
+            ),
+            MqttMessage::new(
+                self.discovery_topic("sensor", &node_id, "external_ip"),
+                entity(
+                    "external_ip",
+                    "External IP",
+                    json!({
+                        "state_topic": state_topic,
+                        "value_template": "{{ value_json.external_ip }}",
+                    }),
+                ),
+                true,
+            ),
+        ];
+        for interface_name in latest_traffic(metrics).keys() {
+            for (direction, name) in [("rx", "received"), ("tx", "sent")] {
+                let object = format!("{}_{}_bytes", object_id(interface_name), direction);
+                messages.push(MqttMessage::new(
+                    self.discovery_topic("sensor", &node_id, &object),
+                    entity(
+                        &object,
+                        &format!("{} {}", interface_name, name),
+                        json!({
+                            "state_topic": state_topic,
+                            "value_template": format!(
+                                "{{{{ value_json.interfaces['{}'].{}_bytes }}}}",
+                                interface_name, direction
+                            ),
+                            "device_class": "data_size",
+                            "unit_of_measurement": "B",
+                            "state_class": "total_increasing",
+                        }),
+                    ),
+                    true,
+                ));
+            }
+        }
+        for (key, device) in &presence.devices {
+            let object = object_id(key);
+            messages.push(MqttMessage::new(
+                self.discovery_topic("device_tracker", &node_id, &object),
+                entity(
+                    &object,
+                    &device.name,
+                    json!({
+                        "state_topic": self.presence_topic(key),
+                        "payload_home": "home",
+                        "payload_not_home": "not_home",
+                        "source_type": "router",
+                    }),
+                ),
+                true,
+            ));
+        }
+        messages
+    }
+
+    /// The state topic, a JSON object read by the sensor templates, and the
+    /// presence topic of every tracked device.
+    pub fn state_messages(
+        &self,
+        status: &Status,
+        wan: &WANConfiguration,
+        metrics: &[Metrics],
+        presence: &PresenceTracker,
+    ) -> Vec<MqttMessage> {
+        let interfaces: Map<String, Value> = latest_traffic(metrics)
+            .into_iter()
+            .map(|(name, traffic)| {
+                (
+                    name.to_string(),
+                    json!({"rx_bytes": traffic.rx_counter, "tx_bytes": traffic.tx_counter}),
+                )
+            })
+            .collect();
+        let state = json!({
+            "uptime": status.up_time,
+            "reboots": status.number_of_reboots,
+            "external_ip": status.external_ip_address,
+            "software_version": status.software_version,
+            "wan_state": wan.wan_state,
+            "connection_state": wan.connection_state,
+            "wan_ip": wan.ip_address,
+            "wan_ipv6": wan.ipv6_address,
+            "interfaces": interfaces,
+        });
+        let mut messages = vec![
+            MqttMessage::new(self.availability_topic(), AVAILABILITY_ONLINE, self.retain),
+            MqttMessage::new(self.state_topic(), state.to_string(), self.retain),
+        ];
+        for (key, device) in &presence.devices {
+            messages.push(MqttMessage::new(
+                self.presence_topic(key),
+                if device.is_home() { "home" } else { "not_home" },
+                self.retain,
+            ));
+        }
+        messages
+    }
+}
+
+pub struct MqttPublisher {
+    config: MqttConfig,
+    client: AsyncClient,
+}
+
+impl std::fmt::Debug for MqttPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttPublisher")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl MqttPublisher {
+    /// Connects to the broker in the background, with a last will marking
+    /// the Livebox entities unavailable when the exporter goes away.
+    pub fn connect(config: MqttConfig) -> Self {
+        let mut options = MqttOptions::new(
+            format!("{}-{}", env!("CARGO_PKG_NAME"), std::process::id()),
+            config.host.clone(),
+            config.port,
+        );
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some((username, password)) = &config.credentials {
+            options.set_credentials(username, password);
+        }
+        options.set_last_will(LastWill::new(
+            config.availability_topic(),
+            AVAILABILITY_OFFLINE,
+            QoS::AtLeastOnce,
+            config.retain,
+        ));
+        let (client, mut eventloop) = AsyncClient::new(options, 100);
+        tokio::spawn(async move {
+            loop {
+                match eventloop.poll().await {
+                    Ok(event) => debug!("MQTT event: {:?}", event),
+                    Err(ConnectionError::RequestsDone) => break,
+                    Err(e) => {
+                        warn!("MQTT connection error: {}", e);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            }
+        });
+        MqttPublisher { config, client }
+    }
+
+    pub fn config(&self) -> &MqttConfig {
+        &self.config
+    }
+
+    pub async fn publish(&self, messages: Vec<MqttMessage>) -> Result<(), ClientError> {
+        for message in messages {
+            self.client
+                .publish(
+                    message.topic,
+                    QoS::AtLeastOnce,
+                    message.retain,
+                    message.payload,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::livebox_client_rs::metrics::DeviceMetrics;
+    use rumqttd::{
+        local::LinkRx, Broker, Config, ConnectionSettings, Notification, RouterConfig,
+        ServerSettings,
+    };
+    use std::{collections::HashMap, net::TcpListener};
+
+    fn config(port: u16) -> MqttConfig {
+        MqttConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            credentials: Some(("homeassistant".to_string(), "secret".to_string())),
+            topic_prefix: "livebox".to_string(),
+            discovery_prefix: "homeassistant".to_string(),
+            retain: false,
+        }
+    }
+
+    fn status() -> Status {
+        serde_json::from_value(json!({
+            "Manufacturer": "Sagemcom",
+            "ManufacturerOUI": "44A61E",
+            "ModelName": "SagemcomFast5360",
+            "Description": "SagemcomFast5360 Sagemcom fr",
+            "ProductClass": "Livebox 5",
+            "SerialNumber": "AN2022410620075",
+            "HardwareVersion": "SG_LB5_1.2.1",
+            "SoftwareVersion": "SG50_sip-fr-6.62.12.1",
+            "RescueVersion": "SG50_sip-fr-6.52.18.1",
+            "ModemFirmwareVersion": "",
+            "EnabledOptions": "",
+            "AdditionalHardwareVersion": "",
+            "AdditionalSoftwareVersion": "g6-f-sip-fr",
+            "SpecVersion": "1.1",
+            "ProvisioningCode": "HASH.3222.2827",
+            "UpTime": 1589,
+            "FirstUseDate": "0001-01-01T00:00:00Z",
+            "DeviceLog": "",
+            "VendorConfigFileNumberOfEntries": 1,
+            "ManufacturerURL": "http://www.sagemcom.com/",
+            "Country": "fr",
+            "ExternalIPAddress": "90.0.0.1",
+            "DeviceStatus": "Up",
+            "NumberOfReboots": 12,
+            "UpgradeOccurred": false,
+            "ResetOccurred": false,
+            "RestoreOccurred": false,
+            "StandbyOccurred": false,
+            "X_SOFTATHOME-COM_AdditionalSoftwareVersions": "",
+            "BaseMAC": "44:A6:1E:00:00:01",
+        }))
+        .unwrap()
+    }
+
+    fn wan() -> WANConfiguration {
+        serde_json::from_value(json!({
+            "WanState": "up",
+            "LinkType": "gpon",
+            "LinkState": "up",
+            "MACAddress": "44:A6:1E:00:00:02",
+            "Protocol": "dhcp",
+            "ConnectionState": "Bound",
+            "LastConnectionError": "None",
+            "IPAddress": "90.0.0.1",
+            "RemoteGateway": "90.0.0.254",
+            "DNSServers": "80.10.246.2,81.253.149.10",
+            "IPv6Address": "2a01:cb00::1",
+            "IPv6DelegatedPrefix": "2a01:cb00::/56",
+        }))
+        .unwrap()
+    }
+
+    fn metrics() -> Vec<Metrics> {
+        let traffic = |rx_counter, tx_counter, timestamp| TrafficData {
+            rx_counter,
+            tx_counter,
+            timestamp,
+        };
+        vec![Metrics {
+            status: HashMap::from([(
+                "eth0".to_string(),
+                DeviceMetrics {
+                    traffic: vec![traffic(100, 200, 1000), traffic(150, 250, 1030)],
+                },
+            )]),
+        }]
+    }
+
+    fn presence() -> PresenceTracker {
+        let mut presence = PresenceTracker::default();
+        presence.observe("AA:BB:CC:DD:EE:01", "phone", true, 100, 600);
+        presence
+    }
+
+    #[test]
+    fn test_parse_broker() {
+        assert_eq!(parse_broker("mqtt.lan"), Ok(("mqtt.lan".to_string(), 1883)));
+        assert_eq!(
+            parse_broker("mqtt://192.168.1.10:1884"),
+            Ok(("192.168.1.10".to_string(), 1884))
+        );
+        assert!(parse_broker("mqtt.lan:port").is_err());
+        assert!(parse_broker("").is_err());
+        assert_eq!(parse_broker("::1"), Ok(("[::1]".to_string(), 1883)));
+        assert_eq!(
+            parse_broker("[fd00::10]:1884"),
+            Ok(("[fd00::10]".to_string(), 1884))
+        );
+        assert_eq!(
+            parse_broker("mqtt://[fd00::10]"),
+            Ok(("[fd00::10]".to_string(), 1883))
+        );
+        assert!(parse_broker("[fd00::10").is_err());
+        assert!(parse_broker("fd00::zz:1884").is_err());
+    }
+
+    #[test]
+    fn test_discovery_messages() {
+        let messages = config(1883).discovery_messages(&status(), &metrics(), &presence());
+        let topics: Vec<&str> = messages.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            vec![
+                "homeassistant/binary_sensor/livebox_an2022410620075/wan/config",
+                "homeassistant/sensor/livebox_an2022410620075/uptime/config",
+                "homeassistant/sensor/livebox_an2022410620075/reboots/config",
+                "homeassistant/sensor/livebox_an2022410620075/connection_state/config",
+                "homeassistant/sensor/livebox_an2022410620075/external_ip/config",
+                "homeassistant/sensor/livebox_an2022410620075/eth0_rx_bytes/config",
+                "homeassistant/sensor/livebox_an2022410620075/eth0_tx_bytes/config",
+                "homeassistant/device_tracker/livebox_an2022410620075/aa_bb_cc_dd_ee_01/config",
+            ]
+        );
+        let rx: Value = serde_json::from_str(&messages[5].payload).unwrap();
+        assert_eq!(rx["unique_id"], "livebox_an2022410620075_eth0_rx_bytes");
+        assert_eq!(
+            rx["value_template"],
+            "{{ value_json.interfaces['eth0'].rx_bytes }}"
+        );
+        assert_eq!(rx["availability_topic"], "livebox/availability");
+        assert_eq!(rx["device"]["identifiers"][0], "livebox_an2022410620075");
+        assert_eq!(rx["device"]["model"], "SagemcomFast5360");
+        let tracker: Value = serde_json::from_str(&messages[7].payload).unwrap();
+        assert_eq!(tracker["name"], "phone");
+        assert_eq!(tracker["state_topic"], "livebox/presence/aa_bb_cc_dd_ee_01");
+        assert_eq!(tracker["source_type"], "router");
+    }
+
+    #[test]
+    fn test_state_messages() {
+        let messages = config(1883).state_messages(&status(), &wan(), &metrics(), &presence());
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[0],
+            MqttMessage::new("livebox/availability".to_string(), "online", false)
+        );
+        assert_eq!(messages[1].topic, "livebox/state");
+        assert_eq!(
+            serde_json::from_str::<Value>(&messages[1].payload).unwrap(),
+            json!({
+                "uptime": 1589,
+                "reboots": 12,
+                "external_ip": "90.0.0.1",
+                "software_version": "SG50_sip-fr-6.62.12.1",
+                "wan_state": "up",
+                "connection_state": "Bound",
+                "wan_ip": "90.0.0.1",
+                "wan_ipv6": "2a01:cb00::1",
+                "interfaces": {"eth0": {"rx_bytes": 150, "tx_bytes": 250}},
+            })
+        );
+        assert_eq!(
+            messages[2],
+            MqttMessage::new(
+                "livebox/presence/aa_bb_cc_dd_ee_01".to_string(),
+                "home",
+                false
+            )
+        );
+    }
+
+    /// An embedded broker requiring the test credentials, listening on a free
+    /// port.
+    fn start_broker() -> (u16, Broker) {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = ServerSettings {
+            name: "v4".to_string(),
+            listen: ([127, 0, 0, 1], port).into(),
+            tls: None,
+            next_connection_delay_ms: 1,
+            connections: ConnectionSettings {
+                connection_timeout_ms: 5000,
+                max_payload_size: 1024 * 1024,
+                max_inflight_count: 100,
+                auth: Some(HashMap::from([(
+                    "homeassistant".to_string(),
+                    "secret".to_string(),
+                )])),
+                external_auth: None,
+                dynamic_filters: true,
+            },
+        };
+        let config = Config {
+            router: RouterConfig {
+                max_connections: 10,
+                max_outgoing_packet_count: 200,
+                max_segment_size: 1024 * 1024,
+                max_segment_count: 10,
+                ..Default::default()
+            },
+            v4: Some(HashMap::from([("v4".to_string(), server)])),
+            ..Default::default()
+        };
+        (port, Broker::new(config))
+    }
+
+    /// Reads the next publish forwarded to the link.
+    async fn next_publish(link: &mut LinkRx) -> (String, String, bool) {
+        loop {
+            let notification = tokio::time::timeout(Duration::from_secs(10), link.next())
+                .await
+                .unwrap()
+                .unwrap();
+            if let Some(Notification::Forward(forward)) = notification {
+                return (
+                    String::from_utf8(forward.publish.topic.to_vec()).unwrap(),
+                    String::from_utf8(forward.publish.payload.to_vec()).unwrap(),
+                    forward.publish.retain,
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let (port, mut broker) = start_broker();
+        let (mut live_tx, mut live_rx) = broker.link("live").unwrap();
+        let (mut late_tx, mut late_rx) = broker.link("late").unwrap();
+        std::thread::spawn(move || broker.start().unwrap());
+        live_tx.subscribe("#").unwrap();
+        while tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let publisher = MqttPublisher::connect(config(port));
+        let config = publisher.config().clone();
+        let discovery = config.discovery_messages(&status(), &metrics(), &presence());
+        let mut messages = discovery.clone();
+        messages.extend(config.state_messages(&status(), &wan(), &metrics(), &presence()));
+        publisher.publish(messages.clone()).await.unwrap();
+        for message in &messages {
+            let (topic, payload, _) = next_publish(&mut live_rx).await;
+            assert_eq!(topic, message.topic);
+            assert_eq!(payload, message.payload);
+        }
+
+        // A subscriber coming later, as Home Assistant after a restart, still
+        // gets the discovery configs.
+        late_tx.subscribe("homeassistant/#").unwrap();
+        let mut retained = Vec::new();
+        for _ in &discovery {
+            let (topic, _, retain) = next_publish(&mut late_rx).await;
+            assert!(retain);
+            retained.push(topic);
+        }
+        retained.sort();
+        let mut expected: Vec<String> = discovery.into_iter().map(|m| m.topic).collect();
+        expected.sort();
+        assert_eq!(retained, expected);
+    }
+}
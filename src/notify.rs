@@ -0,0 +1,370 @@
+use crate::{
+    http::{https_client, HttpsClient},
+    inventory::{DeviceInventory, InventoryDevice},
+};
+use chrono::NaiveTime;
+use hyper::{
+    header::{CONTENT_TYPE, USER_AGENT},
+    Body, Method, Request,
+};
+use log::{debug, info, warn};
+use serde_json::json;
+use std::sync::Mutex;
+
+const NOTIFICATION_TITLE: &str = "New device on the Livebox";
+
+/// Sends of a pending device before giving up on the notifiers that keep
+/// failing, e.g. a wrong url or a revoked token.
+const MAX_NOTIFICATION_ATTEMPTS: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notifier {
+    /// POST of the device as a JSON object.
+    Webhook { url: String },
+    /// ntfy topic url, e.g. `https://ntfy.sh/my-topic`.
+    Ntfy { url: String },
+    /// Gotify message url with its token, `https://host/message?token=...`.
+    Gotify { url: String },
+    /// Slack compatible incoming webhook.
+    Slack { url: String },
+}
+
+fn message(device: &InventoryDevice) -> String {
+    let mut message = format!("{} ({})", device.name, device.key);
+    if !device.ip_address.is_empty() {
+        message.push_str(&format!(" joined with ip {}", device.ip_address));
+    } else {
+        message.push_str(" joined");
+    }
+    if !device.device_type.is_empty() {
+        message.push_str(&format!(", type {}", device.device_type));
+    }
+    message
+}
+
+impl Notifier {
+    fn url(&self) -> &str {
+        match self {
+            Notifier::Webhook { url }
+            | Notifier::Ntfy { url }
+            | Notifier::Gotify { url }
+            | Notifier::Slack { url } => url,
+        }
+    }
+
+    fn request(&self, device: &InventoryDevice) -> Request<Body> {
+        let builder = Request::builder()
+            .method(Method::POST)
+            .uri(self.url())
+            .header(
+                USER_AGENT,
+                format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            );
+        let (builder, body) = match self {
+            Notifier::Webhook { .. } => (
+                builder.header(CONTENT_TYPE, "application/json"),
+                json!({
+                    "event": "new_device",
+                    "key": device.key,
+                    "name": device.name,
+                    "device_type": device.device_type,
+                    "ip_address": device.ip_address,
+                    "first_seen": device.first_seen,
+                })
+                .to_string(),
+            ),
+            Notifier::Ntfy { .. } => (
+                builder
+                    .header("Title", NOTIFICATION_TITLE)
+                    .header("Tags", "warning"),
+                message(device),
+            ),
+            Notifier::Gotify { .. } => (
+                builder.header(CONTENT_TYPE, "application/json"),
+                json!({
+                    "title": NOTIFICATION_TITLE,
+                    "message": message(device),
+                    "priority": 5,
+                })
+                .to_string(),
+            ),
+            Notifier::Slack { .. } => (
+                builder.header(CONTENT_TYPE, "application/json"),
+                json!({ "text": format!("{}: {}", NOTIFICATION_TITLE, message(device)) })
+                    .to_string(),
+            ),
+        };
+        builder
+            .body(Body::from(body))
+            .expect("Could not build request.")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parses `HH:MM-HH:MM`, the range may span midnight.
+    pub fn parse(range: &str) -> Result<Self, String> {
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("expected HH:MM-HH:MM, got {}", range))?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|e| format!("{}: {}", time, e))
+        };
+        Ok(QuietHours {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeviceNotifier {
+    notifiers: Vec<Notifier>,
+    allowlist: Vec<String>,
+    quiet_hours: Option<QuietHours>,
+    client: HttpsClient,
+}
+
+impl DeviceNotifier {
+    pub fn new(
+        notifiers: Vec<Notifier>,
+        allowlist: Vec<String>,
+        quiet_hours: Option<QuietHours>,
+    ) -> Self {
+        Self {
+            notifiers,
+            allowlist,
+            quiet_hours,
+            client: https_client(),
+        }
+    }
+
+    /// Allowlisted devices, by name or MAC address, are never notified.
+    pub fn is_allowlisted(&self, device: &InventoryDevice) -> bool {
+        self.allowlist
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(&device.name) || e.eq_ignore_ascii_case(&device.key))
+    }
+
+    async fn send(&self, notifier: &Notifier, device: &InventoryDevice) -> bool {
+        match self.client.request(notifier.request(device)).await {
+            Ok(response) if response.status().is_success() => true,
+            Ok(response) => {
+                warn!(
+                    "Notification to {} answered {}",
+                    notifier.url(),
+                    response.status()
+                );
+                false
+            }
+            Err(e) => {
+                warn!("Could not send notification to {}: {}", notifier.url(), e);
+                false
+            }
+        }
+    }
+
+    /// Sends the pending new devices to every notifier, unless `time` is in
+    /// the quiet hours. Devices stay pending for the notifiers that failed,
+    /// up to `MAX_NOTIFICATION_ATTEMPTS` sends.
+    /// Returns whether the inventory changed.
+    pub async fn flush(&self, inventory: &Mutex<DeviceInventory>, time: NaiveTime) -> bool {
+        if self.quiet_hours.is_some_and(|q| q.contains(time)) {
+            debug!("Quiet hours, notifications are delayed");
+            return false;
+        }
+        let pending = std::mem::take(&mut inventory.lock().unwrap().pending);
+        if pending.is_empty() {
+            return false;
+        }
+        let (mut sent, mut failures) = (0, 0);
+        let mut retries = Vec::new();
+        for mut device in pending {
+            info!("Notifying new device {} ({})", device.name, device.key);
+            for notifier in &self.notifiers {
+                if device.notified.iter().any(|url| url == notifier.url()) {
+                    continue;
+                }
+                if self.send(notifier, &device).await {
+                    device.notified.push(notifier.url().to_string());
+                    sent += 1;
+                } else {
+                    failures += 1;
+                }
+            }
+            if self
+                .notifiers
+                .iter()
+                .any(|notifier| !device.notified.iter().any(|url| url == notifier.url()))
+            {
+                device.attempts += 1;
+                if device.attempts < MAX_NOTIFICATION_ATTEMPTS {
+                    retries.push(device);
+                } else {
+                    warn!(
+                        "Giving up notifying new device {} ({}) after {} attempts",
+                        device.name, device.key, device.attempts
+                    );
+                }
+            }
+        }
+        let mut inventory = inventory.lock().unwrap();
+        retries.append(&mut inventory.pending);
+        inventory.pending = retries;
+        inventory.notifications += sent;
+        inventory.notification_failures += failures;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::{Method::POST, MockServer};
+
+    fn device() -> InventoryDevice {
+        InventoryDevice {
+            key: "AA:BB:CC:DD:EE:03".to_string(),
+            name: "android-3f2a9c".to_string(),
+            device_type: "Mobile".to_string(),
+            ip_address: "192.168.1.23".to_string(),
+            first_seen: 1000,
+            notified: Vec::new(),
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let time = |t| NaiveTime::parse_from_str(t, "%H:%M").unwrap();
+        let night = QuietHours::parse("22:00-07:00").unwrap();
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("12:00")));
+        let lunch = QuietHours::parse("12:00-14:00").unwrap();
+        assert!(lunch.contains(time("13:00")));
+        assert!(!lunch.contains(time("22:00")));
+        assert!(QuietHours::parse("22:00").is_err());
+        assert!(QuietHours::parse("25:00-07:00").is_err());
+    }
+
+    #[test]
+    fn test_is_allowlisted() {
+        let notifier = DeviceNotifier::new(vec![], vec!["aa:bb:cc:dd:ee:03".to_string()], None);
+        assert!(notifier.is_allowlisted(&device()));
+        let notifier = DeviceNotifier::new(vec![], vec!["laptop".to_string()], None);
+        assert!(!notifier.is_allowlisted(&device()));
+    }
+
+    #[tokio::test]
+    async fn test_flush() {
+        let server = MockServer::start();
+        let webhook = server.mock(|when, then| {
+            when.method(POST).path("/webhook").json_body(json!({
+                "event": "new_device",
+                "key": "AA:BB:CC:DD:EE:03",
+                "name": "android-3f2a9c",
+                "device_type": "Mobile",
+                "ip_address": "192.168.1.23",
+                "first_seen": 1000,
+            }));
+            then.status(200);
+        });
+        let ntfy = server.mock(|when, then| {
+            when.method(POST)
+                .path("/livebox")
+                .header("title", NOTIFICATION_TITLE)
+                .body(
+                    "android-3f2a9c (AA:BB:CC:DD:EE:03) joined with ip 192.168.1.23, type Mobile",
+                );
+            then.status(200);
+        });
+        let gotify = server.mock(|when, then| {
+            when.method(POST)
+                .path("/message")
+                .query_param("token", "secret")
+                .body_contains("\"priority\":5");
+            then.status(200);
+        });
+        let slack = server.mock(|when, then| {
+            when.method(POST)
+                .path("/slack")
+                .body_contains("\"text\":\"New device on the Livebox: android-3f2a9c");
+            then.status(500);
+        });
+        let notifier = DeviceNotifier::new(
+            vec![
+                Notifier::Webhook {
+                    url: server.url("/webhook"),
+                },
+                Notifier::Ntfy {
+                    url: server.url("/livebox"),
+                },
+                Notifier::Gotify {
+                    url: server.url("/message?token=secret"),
+                },
+                Notifier::Slack {
+                    url: server.url("/slack"),
+                },
+            ],
+            vec![],
+            Some(QuietHours::parse("22:00-07:00").unwrap()),
+        );
+        let inventory = Mutex::new(DeviceInventory::default());
+        inventory.lock().unwrap().pending.push(device());
+
+        let night = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+        assert!(!notifier.flush(&inventory, night).await);
+        assert_eq!(inventory.lock().unwrap().pending.len(), 1);
+
+        let day = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        assert!(notifier.flush(&inventory, day).await);
+        webhook.assert();
+        ntfy.assert();
+        gotify.assert();
+        slack.assert();
+        {
+            let inventory = inventory.lock().unwrap();
+            assert_eq!(inventory.pending.len(), 1);
+            assert_eq!(inventory.pending[0].notified.len(), 3);
+            assert_eq!(inventory.notifications, 3);
+            assert_eq!(inventory.notification_failures, 1);
+        }
+
+        assert!(notifier.flush(&inventory, day).await);
+        webhook.assert_hits(1);
+        slack.assert_hits(2);
+        {
+            let inventory = inventory.lock().unwrap();
+            assert_eq!(inventory.pending.len(), 1);
+            assert_eq!(inventory.pending[0].attempts, 2);
+            assert_eq!(inventory.notification_failures, 2);
+        }
+
+        // a notifier that keeps failing is given up after the last attempt
+        for _ in 2..MAX_NOTIFICATION_ATTEMPTS {
+            assert!(notifier.flush(&inventory, day).await);
+        }
+        slack.assert_hits(MAX_NOTIFICATION_ATTEMPTS as usize);
+        let inventory = inventory.lock().unwrap();
+        assert!(inventory.pending.is_empty());
+        assert_eq!(
+            inventory.notification_failures,
+            MAX_NOTIFICATION_ATTEMPTS as u64
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use log::{info, warn};
+use std::{collections::HashMap, fs, path::Path};
+
+#[derive(Debug, Default)]
+pub struct OuiDatabase {
+    vendors: HashMap<[u8; 3], String>,
+}
+
+impl OuiDatabase {
+    /// Loads the IEEE registry, either the `oui.csv` or the `oui.txt` format
+    /// from https://standards-oui.ieee.org/oui/. No vendor is known when the
+    /// file can not be read.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                let database = Self::parse(&content);
+                info!(
+                    "Loaded {} OUI vendors from {}",
+                    database.vendors.len(),
+                    path.display()
+                );
+                database
+            }
+            Err(e) => {
+                warn!("Could not read OUI registry {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut vendors = HashMap::new();
+        for line in content.lines() {
+            let entry = if let Some((prefix, vendor)) = line.split_once("(hex)") {
+                parse_prefix(prefix).map(|p| (p, vendor.trim()))
+            } else {
+                let fields = split_csv(line);
+                match fields.as_slice() {
+                    [_, prefix, vendor, ..] => parse_prefix(prefix).map(|p| (p, *vendor)),
+                    _ => None,
+                }
+            };
+            if let Some((prefix, vendor)) = entry {
+                vendors.insert(prefix, vendor.to_string());
+            }
+        }
+        OuiDatabase { vendors }
+    }
+
+    pub fn vendor(&self, mac: &[u8; 6]) -> Option<&str> {
+        self.vendors
+            .get(&[mac[0], mac[1], mac[2]])
+            .map(String::as_str)
+    }
+}
+
+/// Parses a `AA:BB:CC:DD:EE:FF` or `AA-BB-CC-DD-EE-FF` address.
+pub fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let octets: Vec<u8> = mac
+        .split([':', '-'])
+        .map(|o| match o.len() {
+            2 => u8::from_str_radix(o, 16).ok(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    octets.try_into().ok()
+}
+
+/// Locally administered addresses are not assigned by a vendor, phones use
+/// them for their randomised MAC addresses.
+pub fn is_locally_administered(mac: &[u8; 6]) -> bool {
+    mac[0] & 0x02 != 0
+}
+
+fn parse_prefix(prefix: &str) -> Option<[u8; 3]> {
+    let hex: String = prefix
+        .trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != ':')
+        .collect();
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(&hex, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+fn split_csv(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                fields.push(line[start..i].trim_matches('"'));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(line[start..].trim_matches('"'));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(
+            parse_mac("B8:27:EB:01:02:03"),
+            Some([0xb8, 0x27, 0xeb, 0x01, 0x02, 0x03])
+        );
+        assert_eq!(
+            parse_mac("b8-27-eb-01-02-03"),
+            Some([0xb8, 0x27, 0xeb, 0x01, 0x02, 0x03])
+        );
+        assert_eq!(parse_mac("ID-android-3f2a9c"), None);
+        assert_eq!(parse_mac("B8:27:EB:01:02"), None);
+    }
+
+    #[test]
+    fn test_is_locally_administered() {
+        assert!(is_locally_administered(
+            &parse_mac("DA:A1:19:01:02:03").unwrap()
+        ));
+        assert!(!is_locally_administered(
+            &parse_mac("B8:27:EB:01:02:03").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_vendor() {
+        let database = OuiDatabase::parse("MA-L,B827EB,Raspberry Pi Foundation,Cambridge GB\n");
+        let mac = parse_mac("B8:27:EB:01:02:03").unwrap();
+        assert_eq!(database.vendor(&mac), Some("Raspberry Pi Foundation"));
+        assert_eq!(
+            database.vendor(&parse_mac("02:00:00:00:00:00").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_registry() {
+        let csv = "Registry,Assignment,Organization Name,Organization Address\n\
+                   MA-L,B827EB,Raspberry Pi Foundation,Mitchell Wood House Caldecote GB CB23 7NU\n\
+                   MA-L,0012FB,\"Samsung Electronics Co.,Ltd\",\"#94-1, Imsoo-Dong Gumi Gyeongbuk KR 730-350\"\n";
+        let database = OuiDatabase::parse(csv);
+        assert_eq!(database.vendors.len(), 2);
+        assert_eq!(
+            database.vendor(&parse_mac("00:12:FB:00:00:01").unwrap()),
+            Some("Samsung Electronics Co.,Ltd")
+        );
+
+        let txt = "B8-27-EB   (hex)\t\tRaspberry Pi Foundation\nB827EB     (base 16)\t\tRaspberry Pi Foundation\n";
+        let database = OuiDatabase::parse(txt);
+        assert_eq!(database.vendors.len(), 1);
+        assert_eq!(
+            database.vendor(&parse_mac("B8:27:EB:00:00:01").unwrap()),
+            Some("Raspberry Pi Foundation")
+        );
+    }
+}
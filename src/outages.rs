@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+pub const OUTAGES_STATE_FILE: &str = "outages.json";
+/// Outages older than this are dropped, the counters keep them.
+const OUTAGES_RETENTION_SECONDS: u64 = 31 * 24 * 3600;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outage {
+    pub start: u64,
+    pub end: Option<u64>,
+    pub connection_state: String,
+    pub last_connection_error: String,
+}
+
+impl Outage {
+    fn overlap(&self, from: u64, to: u64, now: u64) -> u64 {
+        let end = self.end.unwrap_or(now).min(to);
+        end.saturating_sub(self.start.max(from))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutageTracker {
+    pub since: Option<u64>,
+    pub total: u64,
+    pub closed_downtime_seconds: u64,
+    pub outages: Vec<Outage>,
+}
+
+impl OutageTracker {
+    fn ongoing(&mut self) -> Option<&mut Outage> {
+        self.outages.last_mut().filter(|o| o.end.is_none())
+    }
+
+    /// Records the WAN state seen at `now`. Returns whether an outage started
+    /// or ended.
+    pub fn observe(
+        &mut self,
+        up: bool,
+        connection_state: &str,
+        last_connection_error: &str,
+        now: u64,
+    ) -> bool {
+        self.since.get_or_insert(now);
+        if up {
+            let Some(outage) = self.ongoing() else {
+                return false;
+            };
+            outage.end = Some(now);
+            let duration = now.saturating_sub(outage.start);
+            self.closed_downtime_seconds += duration;
+            self.outages.retain(|o| {
+                o.end
+                    .is_none_or(|end| end + OUTAGES_RETENTION_SECONDS > now)
+            });
+            return true;
+        }
+        match self.ongoing() {
+            Some(outage) => {
+                // Keep the most meaningful error seen during the outage.
+                if !last_connection_error.is_empty() {
+                    outage.last_connection_error = last_connection_error.to_string();
+                }
+                false
+            }
+            None => {
+                self.total += 1;
+                self.outages.push(Outage {
+                    start: now,
+                    end: None,
+                    connection_state: connection_state.to_string(),
+                    last_connection_error: last_connection_error.to_string(),
+                });
+                true
+            }
+        }
+    }
+
+    pub fn downtime_seconds(&self, now: u64) -> u64 {
+        self.closed_downtime_seconds
+            + self
+                .outages
+                .iter()
+                .filter(|o| o.end.is_none())
+                .map(|o| now.saturating_sub(o.start))
+                .sum::<u64>()
+    }
+
+    /// Share of the last `window` seconds the WAN was up, counted from the
+    /// first observation when the tracker is younger than the window.
+    pub fn availability(&self, window: u64, now: u64) -> f64 {
+        let from = now.saturating_sub(window).max(self.since.unwrap_or(now));
+        let observed = now.saturating_sub(from);
+        if observed == 0 {
+            return 1.0;
+        }
+        let down: u64 = self.outages.iter().map(|o| o.overlap(from, now, now)).sum();
+        observed.saturating_sub(down) as f64 / observed as f64
+    }
+
+    pub fn to_json(&self, now: u64) -> Value {
+        Value::Array(
+            self.outages
+                .iter()
+                .rev()
+                .map(|o| {
+                    json!({
+                        "start": o.start,
+                        "end": o.end,
+                        "duration_seconds": o.end.unwrap_or(now).saturating_sub(o.start),
+                        "connection_state": o.connection_state,
+                        "last_connection_error": o.last_connection_error,
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outage_tracker_observe() {
+        let mut tracker = OutageTracker::default();
+        assert!(!tracker.observe(true, "Connected", "", 1000));
+        assert!(tracker.observe(false, "Connecting", "", 1100));
+        assert!(!tracker.observe(false, "Connecting", "ERROR_NO_CARRIER", 1110));
+        assert_eq!(tracker.downtime_seconds(1120), 20);
+        assert!(tracker.observe(true, "Connected", "", 1130));
+        assert!(!tracker.observe(true, "Connected", "", 1140));
+        assert_eq!(tracker.total, 1);
+        assert_eq!(tracker.downtime_seconds(2000), 30);
+        assert_eq!(tracker.outages[0].end, Some(1130));
+        assert_eq!(tracker.outages[0].last_connection_error, "ERROR_NO_CARRIER");
+    }
+
+    #[test]
+    fn test_outage_tracker_availability() {
+        let mut tracker = OutageTracker::default();
+        tracker.observe(true, "Connected", "", 0);
+        tracker.observe(false, "Connecting", "", 100);
+        tracker.observe(true, "Connected", "", 200);
+        assert_eq!(tracker.availability(1000, 1000), 0.9);
+        assert_eq!(tracker.availability(500, 1000), 1.0);
+        assert_eq!(OutageTracker::default().availability(1000, 1000), 1.0);
+    }
+
+    #[test]
+    fn test_outage_tracker_to_json() {
+        let mut tracker = OutageTracker::default();
+        tracker.observe(false, "Connecting", "ERROR_NO_CARRIER", 100);
+        let json = tracker.to_json(160);
+        assert_eq!(json[0]["duration_seconds"], 60);
+        assert_eq!(json[0]["end"], Value::Null);
+        assert_eq!(json[0]["last_connection_error"], "ERROR_NO_CARRIER");
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+
+pub const PRESENCE_STATE_FILE: &str = "presence.json";
+const PRESENCE_HISTORY_SIZE: usize = 50;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevicePresence {
+    pub name: String,
+    pub last_seen: u64,
+    pub last_arrival: u64,
+    pub closed_seconds: u64,
+    pub sessions: VecDeque<Session>,
+}
+
+impl DevicePresence {
+    pub fn is_home(&self) -> bool {
+        self.sessions.back().is_some_and(|s| s.end.is_none())
+    }
+
+    /// Time spent at home up to the last time the device was seen, so that
+    /// the total does not go down when a session is closed.
+    pub fn presence_seconds(&self) -> u64 {
+        let open = match self.sessions.back() {
+            Some(Session { start, end: None }) => self.last_seen.saturating_sub(*start),
+            _ => 0,
+        };
+        self.closed_seconds + open
+    }
+
+    /// Opens a session when the device shows up, and closes it once the
+    /// device has been gone for `debounce` seconds. The session then ends
+    /// when the device was last seen. Returns whether a session changed.
+    fn observe(&mut self, active: bool, now: u64, debounce: u64) -> bool {
+        if active {
+            self.last_seen = now;
+            if self.is_home() {
+                return false;
+            }
+            self.last_arrival = now;
+            self.sessions.push_back(Session {
+                start: now,
+                end: None,
+            });
+            while self.sessions.len() > PRESENCE_HISTORY_SIZE {
+                self.sessions.pop_front();
+            }
+            return true;
+        }
+        if !self.is_home() || now.saturating_sub(self.last_seen) < debounce {
+            return false;
+        }
+        let last_seen = self.last_seen;
+        let session = self.sessions.back_mut().unwrap();
+        session.end = Some(last_seen);
+        self.closed_seconds += last_seen.saturating_sub(session.start);
+        true
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PresenceTracker {
+    pub devices: BTreeMap<String, DevicePresence>,
+}
+
+impl PresenceTracker {
+    pub fn observe(
+        &mut self,
+        key: &str,
+        name: &str,
+        active: bool,
+        now: u64,
+        debounce: u64,
+    ) -> bool {
+        let presence = self.devices.entry(key.to_ascii_lowercase()).or_default();
+        presence.name = name.to_string();
+        presence.observe(active, now, debounce)
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.devices
+                .iter()
+                .map(|(key, presence)| {
+                    json!({
+                        "key": key,
+                        "name": presence.name,
+                        "home": presence.is_home(),
+                        "last_seen": presence.last_seen,
+                        "last_arrival": presence.last_arrival,
+                        "presence_seconds": presence.presence_seconds(),
+                        "sessions": presence.sessions.iter().rev().collect::<Vec<_>>(),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_with_debounce() {
+        let mut tracker = PresenceTracker::default();
+        assert!(tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 100, 600));
+        assert!(!tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 200, 600));
+        // the phone sleeps, but comes back before the debounce
+        assert!(!tracker.observe("AA:BB:CC:DD:EE:01", "phone", false, 500, 600));
+        assert!(!tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 700, 600));
+        let presence = &tracker.devices["aa:bb:cc:dd:ee:01"];
+        assert!(presence.is_home());
+        assert_eq!(presence.sessions.len(), 1);
+        assert_eq!(presence.presence_seconds(), 600);
+
+        assert!(!tracker.observe("AA:BB:CC:DD:EE:01", "phone", false, 1000, 600));
+        assert!(tracker.observe("AA:BB:CC:DD:EE:01", "phone", false, 1300, 600));
+        let presence = &tracker.devices["aa:bb:cc:dd:ee:01"];
+        assert!(!presence.is_home());
+        assert_eq!(presence.sessions[0].end, Some(700));
+        assert_eq!(presence.presence_seconds(), 600);
+
+        assert!(tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 2000, 600));
+        let presence = &tracker.devices["aa:bb:cc:dd:ee:01"];
+        assert_eq!(presence.last_arrival, 2000);
+        assert!(!tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 2100, 600));
+        let presence = &tracker.devices["aa:bb:cc:dd:ee:01"];
+        assert_eq!(presence.presence_seconds(), 700);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut tracker = PresenceTracker::default();
+        tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 100, 600);
+        tracker.observe("AA:BB:CC:DD:EE:01", "phone", true, 400, 600);
+        assert_eq!(
+            tracker.to_json(),
+            json!([{
+                "key": "aa:bb:cc:dd:ee:01",
+                "name": "phone",
+                "home": true,
+                "last_seen": 400,
+                "last_arrival": 100,
+                "presence_seconds": 300,
+                "sessions": [{"start": 100, "end": null}],
+            }])
+        );
+    }
+}
@@ -0,0 +1,677 @@
+use crate::http::{https_client, url_encode, HttpsClient};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hyper::{
+    header::{HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE, USER_AGENT},
+    Body, Method, Request, StatusCode,
+};
+use log::{debug, info, warn};
+use prost::Message;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+
+pub const PUSH_BUFFER_DIR: &str = "push-buffer";
+
+/// Delay before the first retry of a failed push, doubled on each retry.
+const PUSH_RETRY_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_secs(1)
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushTarget {
+    /// Prometheus Pushgateway, metrics are PUT to `<url>/metrics/job/<job>`,
+    /// or `<url>/metrics/job@base64/<job>` for a job containing a `/`.
+    Pushgateway { url: String },
+    /// Prometheus remote_write endpoint, e.g. `http://prometheus:9090/api/v1/write`.
+    RemoteWrite { url: String },
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// A sample line of the text exposition format.
+#[derive(Debug, Clone, PartialEq)]
+struct TextSample<'a> {
+    series: &'a str,
+    labels: Vec<(String, String)>,
+    value: f64,
+    timestamp: Option<i64>,
+}
+
+fn parse_sample(line: &str) -> Option<TextSample<'_>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let name_end = line.find(['{', ' '])?;
+    let mut labels = vec![("__name__".to_string(), line[..name_end].to_string())];
+    let mut series_end = name_end;
+    if line[name_end..].starts_with('{') {
+        let mut chars = line.char_indices().skip(name_end + 1).peekable();
+        loop {
+            let (start, c) = chars.next()?;
+            if c == '}' {
+                series_end = start + 1;
+                break;
+            }
+            if c == ',' || c == ' ' {
+                continue;
+            }
+            let mut name = String::from(c);
+            for (_, c) in chars.by_ref() {
+                if c == '=' {
+                    break;
+                }
+                name.push(c);
+            }
+            if chars.next()?.1 != '"' {
+                return None;
+            }
+            let mut value = String::new();
+            loop {
+                match chars.next()?.1 {
+                    '"' => break,
+                    '\\' => match chars.next()?.1 {
+                        'n' => value.push('\n'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+            labels.push((name.trim().to_string(), value));
+        }
+    }
+    let mut fields = line[series_end..].split_whitespace();
+    let value = fields.next()?.parse().ok()?;
+    let timestamp = match fields.next() {
+        Some(timestamp) => Some(timestamp.parse().ok()?),
+        None => None,
+    };
+    Some(TextSample {
+        series: &line[..series_end],
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+/// The Pushgateway refuses timestamps and repeated series, so only the last
+/// sample of each series is kept, without its timestamp.
+fn pushgateway_body(rendered: &str) -> Vec<u8> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut series_index: HashMap<&str, usize> = HashMap::new();
+    for line in rendered.lines() {
+        match parse_sample(line) {
+            Some(sample) => {
+                let line = format!(
+                    "{} {}",
+                    sample.series,
+                    line[sample.series.len()..]
+                        .split_whitespace()
+                        .next()
+                        .unwrap()
+                );
+                match series_index.get(sample.series) {
+                    Some(index) => lines[*index] = line,
+                    None => {
+                        series_index.insert(sample.series, lines.len());
+                        lines.push(line);
+                    }
+                }
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+    let mut body = lines.join("\n");
+    body.push('\n');
+    body.into_bytes()
+}
+
+/// Snappy compressed protobuf `WriteRequest`, with sorted labels and the
+/// samples of a series in time order. `job` is added to every series.
+fn remote_write_body(rendered: &str, job: &str, now_ms: i64) -> Vec<u8> {
+    let mut series: BTreeMap<Vec<(String, String)>, Vec<Sample>> = BTreeMap::new();
+    for sample in rendered.lines().filter_map(parse_sample) {
+        let mut labels = sample.labels;
+        if !labels.iter().any(|(name, _)| name == "job") {
+            labels.push(("job".to_string(), job.to_string()));
+        }
+        labels.sort();
+        series.entry(labels).or_default().push(Sample {
+            value: sample.value,
+            timestamp: sample.timestamp.unwrap_or(now_ms),
+        });
+    }
+    let request = WriteRequest {
+        timeseries: series
+            .into_iter()
+            .map(|(labels, mut samples)| {
+                samples.sort_by_key(|s| s.timestamp);
+                samples.dedup_by_key(|s| s.timestamp);
+                TimeSeries {
+                    labels: labels
+                        .into_iter()
+                        .map(|(name, value)| Label { name, value })
+                        .collect(),
+                    samples,
+                }
+            })
+            .collect(),
+    };
+    snap::raw::Encoder::new()
+        .compress_vec(&request.encode_to_vec())
+        .expect("Could not compress the remote write request.")
+}
+
+/// The job part of the Pushgateway url. A `/` can not be escaped in a path
+/// segment, so such jobs use the Pushgateway base64url form.
+fn pushgateway_job_path(job: &str) -> String {
+    if job.contains('/') {
+        format!("job@base64/{}", URL_SAFE_NO_PAD.encode(job))
+    } else {
+        format!("job/{}", url_encode(job))
+    }
+}
+
+/// Parses a `Name: value` header, rejecting names and values that are not
+/// valid in HTTP.
+pub fn parse_header(header: &str) -> Result<(String, String), String> {
+    let (name, value) = header
+        .split_once(':')
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .ok_or_else(|| format!("expected Name: value, got {}", header))?;
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| format!("invalid header name {}: {}", name, e))?;
+    HeaderValue::from_str(value).map_err(|e| format!("invalid header value {}: {}", value, e))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Remote write payloads that could not be sent, oldest first, kept as files
+/// in `dir` so they survive a restart.
+#[derive(Debug, Default)]
+pub struct PushBuffer {
+    dir: Option<PathBuf>,
+    entries: VecDeque<(String, Vec<u8>)>,
+    max_entries: usize,
+    /// Keeps the names of pushes buffered in the same millisecond apart.
+    sequence: u64,
+}
+
+impl PushBuffer {
+    pub fn load(dir: Option<PathBuf>, max_entries: usize) -> Self {
+        let mut entries = Vec::new();
+        if let Some(dir) = &dir {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !name.ends_with(".bin") {
+                    continue;
+                }
+                match fs::read(entry.path()) {
+                    Ok(body) => entries.push((name, body)),
+                    Err(e) => warn!("Could not read buffered push {}: {}", name, e),
+                }
+            }
+            entries.sort();
+            if !entries.is_empty() {
+                info!(
+                    "Loaded {} buffered pushes from {}",
+                    entries.len(),
+                    dir.display()
+                );
+            }
+        }
+        PushBuffer {
+            dir,
+            entries: entries.into(),
+            max_entries,
+            sequence: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn push(&mut self, now_ms: i64, body: Vec<u8>) {
+        let name = format!("{:020}-{:06}.bin", now_ms, self.sequence % 1_000_000);
+        self.sequence += 1;
+        if let Some(dir) = &self.dir {
+            if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(dir.join(&name), &body))
+            {
+                warn!("Could not buffer push in {}: {}", dir.display(), e);
+            }
+        }
+        self.entries.push_back((name, body));
+        while self.entries.len() > self.max_entries {
+            warn!("Push buffer is full, dropping the oldest push");
+            self.pop();
+        }
+    }
+
+    fn front(&self) -> Option<&[u8]> {
+        self.entries.front().map(|(_, body)| body.as_slice())
+    }
+
+    fn pop(&mut self) {
+        if let Some((name, _)) = self.entries.pop_front() {
+            if let Some(dir) = &self.dir {
+                if let Err(e) = fs::remove_file(dir.join(&name)) {
+                    warn!("Could not remove buffered push {}: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PushError {
+    /// Network errors, 5xx and 429, worth retrying later.
+    Retryable(String),
+    /// Any other answer, the payload will never be accepted.
+    Rejected(String),
+}
+
+#[derive(Debug)]
+pub struct Pusher {
+    target: PushTarget,
+    job: String,
+    headers: Vec<(String, String)>,
+    retries: u32,
+    buffer: PushBuffer,
+    client: HttpsClient,
+}
+
+impl Pusher {
+    pub fn new(
+        target: PushTarget,
+        job: &str,
+        headers: Vec<(String, String)>,
+        retries: u32,
+        buffer: PushBuffer,
+    ) -> Self {
+        Self {
+            target,
+            job: job.to_string(),
+            headers,
+            retries,
+            buffer,
+            client: https_client(),
+        }
+    }
+
+    fn request(&self, body: &[u8]) -> Result<Request<Body>, String> {
+        let builder = match &self.target {
+            PushTarget::Pushgateway { url } => Request::builder()
+                .method(Method::PUT)
+                .uri(format!(
+                    "{}/metrics/{}",
+                    url.trim_end_matches('/'),
+                    pushgateway_job_path(&self.job)
+                ))
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4"),
+            PushTarget::RemoteWrite { url } => Request::builder()
+                .method(Method::POST)
+                .uri(url)
+                .header(CONTENT_TYPE, "application/x-protobuf")
+                .header(CONTENT_ENCODING, "snappy")
+                .header("X-Prometheus-Remote-Write-Version", "0.1.0"),
+        };
+        let builder = self.headers.iter().fold(
+            builder.header(
+                USER_AGENT,
+                format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            ),
+            |builder, (name, value)| builder.header(name, value),
+        );
+        builder
+            .body(Body::from(body.to_vec()))
+            .map_err(|e| e.to_string())
+    }
+
+    async fn send(&self, body: &[u8]) -> Result<(), PushError> {
+        let mut delay = PUSH_RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            let request = self.request(body).map_err(|e| {
+                PushError::Rejected(format!("the request could not be built: {}", e))
+            })?;
+            let error = match self.client.request(request).await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response)
+                    if response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    format!("answered {}", response.status())
+                }
+                Ok(response) => {
+                    return Err(PushError::Rejected(format!(
+                        "the receiver answered {}",
+                        response.status()
+                    )))
+                }
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.retries {
+                return Err(PushError::Retryable(error));
+            }
+            debug!("Push failed ({}), retrying in {:?}", error, delay);
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Pushes the rendered metrics. Remote write payloads that can not be
+    /// sent are buffered and sent first on the next push, the Pushgateway
+    /// only keeps the latest push so nothing is buffered for it.
+    pub async fn push(&mut self, rendered: &str, now_ms: i64) -> bool {
+        let body = match self.target {
+            PushTarget::Pushgateway { .. } => pushgateway_body(rendered),
+            PushTarget::RemoteWrite { .. } => remote_write_body(rendered, &self.job, now_ms),
+        };
+        while let Some(buffered) = self.buffer.front() {
+            match self.send(buffered).await {
+                Ok(()) => self.buffer.pop(),
+                Err(PushError::Rejected(e)) => {
+                    warn!("Dropping a buffered push, {}", e);
+                    self.buffer.pop();
+                }
+                Err(PushError::Retryable(e)) => {
+                    warn!(
+                        "Could not push, {} pushes buffered: {}",
+                        self.buffer.len() + 1,
+                        e
+                    );
+                    self.buffer.push(now_ms, body);
+                    return false;
+                }
+            }
+        }
+        match self.send(&body).await {
+            Ok(()) => true,
+            Err(PushError::Retryable(e))
+                if matches!(self.target, PushTarget::RemoteWrite { .. }) =>
+            {
+                warn!("Could not push, buffering it: {}", e);
+                self.buffer.push(now_ms, body);
+                false
+            }
+            Err(PushError::Retryable(e) | PushError::Rejected(e)) => {
+                warn!("Could not push: {}", e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::{
+        Method::{POST, PUT},
+        MockServer,
+    };
+
+    const RENDERED: &str = "# HELP livebox_infos_uptime Livebox uptime\n\
+                            # TYPE livebox_infos_uptime gauge\n\
+                            livebox_infos_uptime{hardware=\"livebox\",serial_number=\"AN2022410620075\"} 12345 1700000000000\n\
+                            # HELP livebox_interface_homelan Livebox interface traffic\n\
+                            # TYPE livebox_interface_homelan gauge\n\
+                            livebox_interface_homelan{interface_name=\"eth0\",direction=\"rx\"} 100 1700000000000\n\
+                            livebox_interface_homelan{interface_name=\"eth0\",direction=\"rx\"} 150 1700000030000\n\
+                            livebox_device_status{device_name=\"TV \\\"salon\\\", 2\"} 1\n";
+
+    fn decode(body: &[u8]) -> WriteRequest {
+        let decompressed = snap::raw::Decoder::new().decompress_vec(body).unwrap();
+        WriteRequest::decode(decompressed.as_slice()).unwrap()
+    }
+
+    fn labels(series: &TimeSeries) -> Vec<(&str, &str)> {
+        series
+            .labels
+            .iter()
+            .map(|l| (l.name.as_str(), l.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let sample = parse_sample("livebox_device_status{device_name=\"TV \\\"salon\\\", 2\",active=\"1\"} 1 1700000000000").unwrap();
+        assert_eq!(
+            sample.series,
+            "livebox_device_status{device_name=\"TV \\\"salon\\\", 2\",active=\"1\"}"
+        );
+        assert_eq!(
+            sample.labels,
+            vec![
+                ("__name__".to_string(), "livebox_device_status".to_string()),
+                ("device_name".to_string(), "TV \"salon\", 2".to_string()),
+                ("active".to_string(), "1".to_string()),
+            ]
+        );
+        assert_eq!(sample.value, 1.0);
+        assert_eq!(sample.timestamp, Some(1700000000000));
+        let sample = parse_sample("livebox_events_total 3").unwrap();
+        assert_eq!(sample.series, "livebox_events_total");
+        assert_eq!(sample.timestamp, None);
+        assert!(parse_sample("# TYPE livebox_events_total counter").is_none());
+        assert!(parse_sample("livebox_events_total{handler=\"NMC\"").is_none());
+    }
+
+    #[test]
+    fn test_pushgateway_body() {
+        let body = String::from_utf8(pushgateway_body(RENDERED)).unwrap();
+        assert_eq!(
+            body,
+            "# HELP livebox_infos_uptime Livebox uptime\n\
+             # TYPE livebox_infos_uptime gauge\n\
+             livebox_infos_uptime{hardware=\"livebox\",serial_number=\"AN2022410620075\"} 12345\n\
+             # HELP livebox_interface_homelan Livebox interface traffic\n\
+             # TYPE livebox_interface_homelan gauge\n\
+             livebox_interface_homelan{interface_name=\"eth0\",direction=\"rx\"} 150\n\
+             livebox_device_status{device_name=\"TV \\\"salon\\\", 2\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn test_remote_write_body() {
+        let request = decode(&remote_write_body(RENDERED, "livebox", 1700000060000));
+        assert_eq!(request.timeseries.len(), 3);
+        let device = &request.timeseries[0];
+        assert_eq!(
+            labels(device),
+            vec![
+                ("__name__", "livebox_device_status"),
+                ("device_name", "TV \"salon\", 2"),
+                ("job", "livebox"),
+            ]
+        );
+        assert_eq!(device.samples[0].timestamp, 1700000060000);
+        let homelan = &request.timeseries[2];
+        assert_eq!(
+            labels(homelan),
+            vec![
+                ("__name__", "livebox_interface_homelan"),
+                ("direction", "rx"),
+                ("interface_name", "eth0"),
+                ("job", "livebox"),
+            ]
+        );
+        assert_eq!(
+            homelan.samples,
+            vec![
+                Sample {
+                    value: 100.0,
+                    timestamp: 1700000000000
+                },
+                Sample {
+                    value: 150.0,
+                    timestamp: 1700000030000
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pushgateway_job_path() {
+        assert_eq!(pushgateway_job_path("livebox"), "job/livebox");
+        assert_eq!(pushgateway_job_path("livebox home"), "job/livebox%20home");
+        assert_eq!(
+            pushgateway_job_path("livebox/home"),
+            "job@base64/bGl2ZWJveC9ob21l"
+        );
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization: Bearer secret"),
+            Ok(("Authorization".to_string(), "Bearer secret".to_string()))
+        );
+        assert!(parse_header("Authorization").is_err());
+        assert!(parse_header(": secret").is_err());
+        assert!(parse_header("X Bad: v").is_err());
+        assert!(parse_header("X-Token: a\u{7f}b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_push_to_pushgateway() {
+        let server = MockServer::start();
+        let pushgateway = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/metrics/job@base64/bGl2ZWJveC9ob21l")
+                .header("authorization", "Bearer secret")
+                .body_contains(
+                    "livebox_interface_homelan{interface_name=\"eth0\",direction=\"rx\"} 150\n",
+                );
+            then.status(200);
+        });
+        let mut pusher = Pusher::new(
+            PushTarget::Pushgateway {
+                url: server.url("/"),
+            },
+            "livebox/home",
+            vec![("Authorization".to_string(), "Bearer secret".to_string())],
+            0,
+            PushBuffer::load(None, 10),
+        );
+        assert!(pusher.push(RENDERED, 1700000060000).await);
+        pushgateway.assert();
+    }
+
+    #[tokio::test]
+    async fn test_push_remote_write_with_buffer() {
+        let dir = std::env::temp_dir().join(format!("livebox-push-{}", std::process::id()));
+        let server = MockServer::start();
+        let mut unavailable = server.mock(|when, then| {
+            when.method(POST).path("/api/v1/write");
+            then.status(503);
+        });
+        let mut pusher = Pusher::new(
+            PushTarget::RemoteWrite {
+                url: server.url("/api/v1/write"),
+            },
+            "livebox",
+            vec![],
+            2,
+            PushBuffer::load(Some(dir.clone()), 10),
+        );
+        assert!(!pusher.push(RENDERED, 1700000060000).await);
+        unavailable.assert_hits(3);
+        // a second push in the same millisecond does not overwrite the first
+        assert!(!pusher.push(RENDERED, 1700000060000).await);
+        assert_eq!(pusher.buffer.len(), 2);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        unavailable.delete();
+
+        // a restart loads the buffered pushes back
+        let buffer = PushBuffer::load(Some(dir.clone()), 10);
+        assert_eq!(buffer.len(), 2);
+        let mut pusher = Pusher::new(pusher.target.clone(), "livebox", vec![], 2, buffer);
+        let receiver = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v1/write")
+                .header("content-encoding", "snappy")
+                .header("content-type", "application/x-protobuf")
+                .header("x-prometheus-remote-write-version", "0.1.0")
+                .matches(|request| {
+                    let request = decode(request.body.as_deref().unwrap_or_default());
+                    request.timeseries.len() == 3
+                });
+            then.status(204);
+        });
+        assert!(pusher.push(RENDERED, 1700000180000).await);
+        receiver.assert_hits(3);
+        assert_eq!(pusher.buffer.len(), 0);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_push_rejected_is_not_buffered() {
+        let server = MockServer::start();
+        let receiver = server.mock(|when, then| {
+            when.method(POST).path("/api/v1/write");
+            then.status(400);
+        });
+        let mut pusher = Pusher::new(
+            PushTarget::RemoteWrite {
+                url: server.url("/api/v1/write"),
+            },
+            "livebox",
+            vec![],
+            2,
+            PushBuffer::load(None, 10),
+        );
+        assert!(!pusher.push(RENDERED, 1700000060000).await);
+        receiver.assert_hits(1);
+        assert_eq!(pusher.buffer.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_push_invalid_request_is_not_buffered() {
+        let mut pusher = Pusher::new(
+            PushTarget::RemoteWrite {
+                url: "http://receiver /api/v1/write".to_string(),
+            },
+            "livebox",
+            vec![],
+            2,
+            PushBuffer::load(None, 10),
+        );
+        assert!(!pusher.push(RENDERED, 1700000060000).await);
+        assert_eq!(pusher.buffer.len(), 0);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{render_livebox_api, render_livebox_metrics, MyOptions};
+use clap::ArgMatches;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{error, info, trace, warn};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+pub async fn run_server(addr: SocketAddr, options: Arc<MyOptions>, matches: ArgMatches) {
+    info!("Listening on http://{}/metrics", addr);
+    let make_service = make_service_fn(move |_| {
+        let options = options.clone();
+        let matches = matches.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                serve_request(request, options.clone(), matches.clone())
+            }))
+        }
+    });
+    if let Err(err) = Server::bind(&addr).serve(make_service).await {
+        error!("{:?}", err);
+        eprintln!("Server failure: {:?}", err);
+    }
+}
+
+async fn serve_request(
+    request: Request<Body>,
+    options: Arc<MyOptions>,
+    matches: ArgMatches,
+) -> Result<Response<Body>, Infallible> {
+    trace!(
+        "serve_request:: uri == {}, method == {}",
+        request.uri().path(),
+        request.method()
+    );
+    let path = request.uri().path().to_string();
+    if request.method() != Method::GET {
+        return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    if path == "/metrics" {
+        return Ok(
+            match render_livebox_metrics(request, options, matches).await {
+                Ok(rendered) => Response::builder()
+                    .status(StatusCode::OK)
+                    .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                    .body(Body::from(rendered))
+                    .unwrap(),
+                Err(err) => {
+                    warn!("internal server error == {:?}", err);
+                    Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(Body::from(err.to_string()))
+                        .unwrap()
+                }
+            },
+        );
+    }
+    Ok(match render_livebox_api(&path, &options) {
+        Some(json) => Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json.to_string()))
+            .unwrap(),
+        None => empty_response(StatusCode::NOT_FOUND),
+    })
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
@@ -0,0 +1,60 @@
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path};
+
+pub fn load<T>(state_dir: Option<&Path>, name: &str) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    let Some(path) = state_dir.map(|dir| dir.join(name)) else {
+        return T::default();
+    };
+    match fs::read(&path) {
+        Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable state file {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(e) => {
+            debug!("No state loaded from {}: {}", path.display(), e);
+            T::default()
+        }
+    }
+}
+
+pub fn save<T>(state_dir: Option<&Path>, name: &str, value: &T)
+where
+    T: Serialize,
+{
+    let Some(dir) = state_dir else {
+        return;
+    };
+    let path = dir.join(name);
+    let tmp_path = dir.join(format!("{}.tmp", name));
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&tmp_path, serde_json::to_vec_pretty(value).unwrap()))
+        .and_then(|_| fs::rename(&tmp_path, &path));
+    if let Err(e) = result {
+        warn!("Could not save state file {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = std::env::temp_dir().join(format!("livebox-state-{}", std::process::id()));
+        let mut value: BTreeMap<String, u64> = BTreeMap::new();
+        value.insert("test".to_string(), 42);
+        save(Some(&dir), "test.json", &value);
+        let loaded: BTreeMap<String, u64> = load(Some(&dir), "test.json");
+        assert_eq!(loaded, value);
+        let missing: BTreeMap<String, u64> = load(Some(&dir), "missing.json");
+        assert!(missing.is_empty());
+        let in_memory: BTreeMap<String, u64> = load(None, "test.json");
+        assert!(in_memory.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// Last value seen of a router property, with how many times and when it
+/// last changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueTracker {
+    #[serde(alias = "software_version")]
+    pub current: Option<String>,
+    pub changes: u64,
+    pub last_change: u64,
+}
+
+impl ValueTracker {
+    /// Records the value seen at `now`. The first value ever seen is not
+    /// counted as a change. Returns whether the state changed.
+    pub fn observe(&mut self, value: &str, now: u64) -> bool {
+        if value.is_empty() || self.current.as_deref() == Some(value) {
+            return false;
+        }
+        if self.current.is_some() {
+            self.changes += 1;
+            self.last_change = now;
+        }
+        self.current = Some(value.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe() {
+        let mut tracker = ValueTracker::default();
+        assert!(tracker.observe("SG30_sip-fr-6.62.12.1", 100));
+        assert_eq!(tracker.changes, 0);
+        assert_eq!(tracker.last_change, 0);
+        assert!(!tracker.observe("SG30_sip-fr-6.62.12.1", 200));
+        assert!(!tracker.observe("", 250));
+        assert!(tracker.observe("SG30_sip-fr-6.64.2.1", 300));
+        assert_eq!(tracker.changes, 1);
+        assert_eq!(tracker.last_change, 300);
+        assert_eq!(tracker.current.as_deref(), Some("SG30_sip-fr-6.64.2.1"));
+    }
+
+    #[test]
+    fn test_deserialize_firmware_state() {
+        let tracker: ValueTracker = serde_json::from_str(
+            r#"{"software_version":"SG30_sip-fr-6.62.12.1","changes":2,"last_change":300}"#,
+        )
+        .unwrap();
+        assert_eq!(tracker.current.as_deref(), Some("SG30_sip-fr-6.62.12.1"));
+        assert_eq!(tracker.changes, 2);
+    }
+}
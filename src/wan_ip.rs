@@ -0,0 +1,91 @@
+use crate::tracker::ValueTracker;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const WAN_IP_STATE_FILE: &str = "wan_ip.json";
+const WAN_IP_HISTORY_SIZE: usize = 100;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpChange {
+    pub address: String,
+    pub since: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpTracker {
+    #[serde(flatten)]
+    pub address: ValueTracker,
+    pub history: VecDeque<IpChange>,
+}
+
+impl IpTracker {
+    /// Records the address seen at `now`, keeping the last addresses in the
+    /// history. Returns whether the state changed.
+    pub fn observe(&mut self, address: &str, now: u64) -> bool {
+        if !self.address.observe(address, now) {
+            return false;
+        }
+        self.history.push_back(IpChange {
+            address: address.to_string(),
+            since: now,
+        });
+        while self.history.len() > WAN_IP_HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        true
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WanIpHistory {
+    pub ipv4: IpTracker,
+    pub ipv6: IpTracker,
+}
+
+impl WanIpHistory {
+    pub fn observe(&mut self, ipv4_address: &str, ipv6_address: &str, now: u64) -> bool {
+        let ipv4_changed = self.ipv4.observe(ipv4_address, now);
+        let ipv6_changed = self.ipv6.observe(ipv6_address, now);
+        ipv4_changed || ipv6_changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_tracker_observe() {
+        let mut tracker = IpTracker::default();
+        assert!(tracker.observe("192.0.2.1", 100));
+        assert_eq!(tracker.address.changes, 0);
+        assert_eq!(tracker.address.last_change, 0);
+        assert!(!tracker.observe("192.0.2.1", 200));
+        assert!(!tracker.observe("", 250));
+        assert!(tracker.observe("192.0.2.2", 300));
+        assert_eq!(tracker.address.changes, 1);
+        assert_eq!(tracker.address.last_change, 300);
+        assert_eq!(tracker.address.current.as_deref(), Some("192.0.2.2"));
+        assert_eq!(tracker.history.len(), 2);
+    }
+
+    #[test]
+    fn test_ip_tracker_state_format() {
+        let tracker: IpTracker = serde_json::from_str(
+            r#"{"current":"192.0.2.1","changes":1,"last_change":300,"history":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(tracker.address.current.as_deref(), Some("192.0.2.1"));
+        assert_eq!(tracker.address.last_change, 300);
+    }
+
+    #[test]
+    fn test_ip_tracker_history_is_bounded() {
+        let mut tracker = IpTracker::default();
+        for i in 0..150 {
+            tracker.observe(&format!("192.0.2.{}", i), i);
+        }
+        assert_eq!(tracker.history.len(), WAN_IP_HISTORY_SIZE);
+        assert_eq!(tracker.address.changes, 149);
+    }
+}